@@ -0,0 +1,212 @@
+//! Storage for DMA circular buffers
+
+use crate::{Burst16, Burst32, Element};
+use core::ops::{Deref, DerefMut};
+
+/// A circular buffer of `N` elements
+///
+/// A DMA channel can only wrap within a buffer if the buffer's length is a power
+/// of two, and if the buffer is aligned to its size in bytes. `CircularBuffer`
+/// guarantees both properties by construction. You can only name a
+/// `CircularBuffer` if `N` is a power of two, and if the buffer is at least two
+/// bytes, and no larger than 64 KiB. The DMA channel cannot wrap within a single
+/// byte. The type's alignment is always its size.
+///
+/// Use `CircularBuffer` with [`set_source_circular_buffer`](crate::channel::set_source_circular_buffer),
+/// [`set_destination_circular_buffer`](crate::channel::set_destination_circular_buffer),
+/// and with the rings in [`peripheral`](crate::peripheral).
+///
+/// `CircularBuffer` dereferences to a slice of its elements.
+///
+/// ```
+/// use imxrt_dma::CircularBuffer;
+///
+/// static mut BUFFER: CircularBuffer<u16, 64> = CircularBuffer::new([0; 64]);
+///
+/// assert_eq!(core::mem::align_of::<CircularBuffer<u16, 64>>(), 128);
+/// ```
+///
+/// A length that is not a power of two does not compile.
+///
+/// ```compile_fail
+/// use imxrt_dma::CircularBuffer;
+///
+/// let buffer: CircularBuffer<u8, 100> = CircularBuffer::new([0; 100]);
+/// ```
+///
+/// Neither does a buffer of one byte.
+///
+/// ```compile_fail
+/// use imxrt_dma::CircularBuffer;
+///
+/// let buffer: CircularBuffer<u8, 1> = CircularBuffer::new([0; 1]);
+/// ```
+#[repr(C)]
+pub struct CircularBuffer<E, const N: usize>
+where
+    E: Element,
+    [E; N]: Circular,
+{
+    _alignment: [<[E; N] as Circular>::Alignment; 0],
+    elements: [E; N],
+}
+
+impl<E, const N: usize> CircularBuffer<E, N>
+where
+    E: Element,
+    [E; N]: Circular,
+{
+    /// Create a circular buffer that holds `elements`
+    pub const fn new(elements: [E; N]) -> Self {
+        Self {
+            _alignment: [],
+            elements,
+        }
+    }
+
+    /// Returns the buffer's elements
+    pub fn into_inner(self) -> [E; N] {
+        self.elements
+    }
+}
+
+impl<E, const N: usize> Deref for CircularBuffer<E, N>
+where
+    E: Element,
+    [E; N]: Circular,
+{
+    type Target = [E];
+    fn deref(&self) -> &Self::Target {
+        &self.elements
+    }
+}
+
+impl<E, const N: usize> DerefMut for CircularBuffer<E, N>
+where
+    E: Element,
+    [E; N]: Circular,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.elements
+    }
+}
+
+impl<E, const N: usize> AsRef<[E]> for CircularBuffer<E, N>
+where
+    E: Element,
+    [E; N]: Circular,
+{
+    fn as_ref(&self) -> &[E] {
+        &self.elements
+    }
+}
+
+impl<E, const N: usize> AsMut<[E]> for CircularBuffer<E, N>
+where
+    E: Element,
+    [E; N]: Circular,
+{
+    fn as_mut(&mut self) -> &mut [E] {
+        &mut self.elements
+    }
+}
+
+/// An array that can be stored in a [`CircularBuffer`]
+///
+/// This trait is sealed. It's implemented for arrays of [`Element`]s whose length
+/// is a power of two, and whose size is at least two bytes, and no larger than 64 KiB.
+pub trait Circular: private::Sealed {
+    /// A type whose alignment is the array's size
+    #[doc(hidden)]
+    type Alignment;
+}
+
+mod private {
+    pub trait Sealed {}
+}
+
+macro_rules! alignments {
+    ($($align:ident = $bytes:literal),* $(,)?) => {
+        $(
+            #[doc(hidden)]
+            #[derive(Clone, Copy)]
+            #[repr(align($bytes))]
+            pub struct $align;
+        )*
+    };
+}
+
+alignments!(
+    Align2 = 2,
+    Align4 = 4,
+    Align8 = 8,
+    Align16 = 16,
+    Align32 = 32,
+    Align64 = 64,
+    Align128 = 128,
+    Align256 = 256,
+    Align512 = 512,
+    Align1024 = 1024,
+    Align2048 = 2048,
+    Align4096 = 4096,
+    Align8192 = 8192,
+    Align16384 = 16384,
+    Align32768 = 32768,
+    Align65536 = 65536,
+);
+
+macro_rules! circular {
+    ($($elem:ty),+; $lengths:tt) => {
+        $(circular!(@impl $elem; $lengths);)+
+    };
+    (@impl $elem:ty; { $($len:literal => $align:ident),* $(,)? }) => {
+        $(
+            impl private::Sealed for [$elem; $len] {}
+            impl Circular for [$elem; $len] {
+                type Alignment = $align;
+            }
+        )*
+    };
+}
+
+circular!(u8, i8; {
+    2 => Align2, 4 => Align4, 8 => Align8,
+    16 => Align16, 32 => Align32, 64 => Align64, 128 => Align128,
+    256 => Align256, 512 => Align512, 1024 => Align1024, 2048 => Align2048,
+    4096 => Align4096, 8192 => Align8192, 16384 => Align16384, 32768 => Align32768,
+    65536 => Align65536,
+});
+
+circular!(u16, i16; {
+    1 => Align2, 2 => Align4, 4 => Align8, 8 => Align16,
+    16 => Align32, 32 => Align64, 64 => Align128, 128 => Align256,
+    256 => Align512, 512 => Align1024, 1024 => Align2048, 2048 => Align4096,
+    4096 => Align8192, 8192 => Align16384, 16384 => Align32768, 32768 => Align65536,
+});
+
+circular!(u32, i32, f32; {
+    1 => Align4, 2 => Align8, 4 => Align16, 8 => Align32,
+    16 => Align64, 32 => Align128, 64 => Align256, 128 => Align512,
+    256 => Align1024, 512 => Align2048, 1024 => Align4096, 2048 => Align8192,
+    4096 => Align16384, 8192 => Align32768, 16384 => Align65536,
+});
+
+circular!(u64, i64, f64; {
+    1 => Align8, 2 => Align16, 4 => Align32, 8 => Align64,
+    16 => Align128, 32 => Align256, 64 => Align512, 128 => Align1024,
+    256 => Align2048, 512 => Align4096, 1024 => Align8192, 2048 => Align16384,
+    4096 => Align32768, 8192 => Align65536,
+});
+
+circular!(Burst16; {
+    1 => Align16, 2 => Align32, 4 => Align64, 8 => Align128,
+    16 => Align256, 32 => Align512, 64 => Align1024, 128 => Align2048,
+    256 => Align4096, 512 => Align8192, 1024 => Align16384, 2048 => Align32768,
+    4096 => Align65536,
+});
+
+circular!(Burst32; {
+    1 => Align32, 2 => Align64, 4 => Align128, 8 => Align256,
+    16 => Align512, 32 => Align1024, 64 => Align2048, 128 => Align4096,
+    256 => Align8192, 512 => Align16384, 1024 => Align32768, 2048 => Align65536,
+});
@@ -0,0 +1,118 @@
+//! Data cache maintenance for DMA buffers
+//!
+//! With the `dcache` feature, the DMA futures clean buffers before the DMA
+//! channel reads them, and invalidate buffers after the DMA channel writes
+//! them. Without the feature, these functions do nothing.
+//!
+//! Maintenance happens on all ARM targets, and assumes a Cortex-M7 core. The
+//! Rust targets for the Cortex-M7 also build for the Cortex-M4, which has no
+//! data cache, so only enable the feature when you build for a Cortex-M7 core.
+//! On other architectures, including the simulator, the `dcache` feature does
+//! nothing.
+
+/// The size of a Cortex-M7 data cache line, in bytes
+#[cfg(all(feature = "dcache", target_arch = "arm"))]
+const LINE_SIZE: usize = 32;
+
+/// Cache maintenance operations, identified by their register address
+#[cfg(all(feature = "dcache", target_arch = "arm"))]
+mod operation {
+    /// Data cache clean by address to the point of coherency
+    pub const DCCMVAC: *mut usize = 0xE000_EF68 as _;
+    /// Data cache invalidate by address to the point of coherency
+    pub const DCIMVAC: *mut usize = 0xE000_EF5C as _;
+    /// Data cache clean and invalidate by address to the point of coherency
+    pub const DCCIMVAC: *mut usize = 0xE000_EF70 as _;
+}
+
+/// Apply a maintenance operation to all cache lines that hold `size` bytes at
+/// `address`
+#[cfg(all(feature = "dcache", target_arch = "arm"))]
+fn maintain(operation: *mut usize, address: usize, size: usize) {
+    use core::arch::asm;
+
+    if size == 0 {
+        return;
+    }
+    let start = address & !(LINE_SIZE - 1);
+    // Safety: the maintenance registers accept any address. The barriers
+    // order the maintenance with the surrounding memory accesses.
+    unsafe {
+        asm!("dsb sy", options(nostack, preserves_flags));
+        for line in (start..address + size).step_by(LINE_SIZE) {
+            operation.write_volatile(line);
+        }
+        asm!("dsb sy", "isb sy", options(nostack, preserves_flags));
+    }
+}
+
+/// Write back any cached data for `buffer`
+///
+/// Use this before the DMA channel reads `buffer`.
+pub(crate) fn clean<E>(buffer: &[E]) {
+    #[cfg(all(feature = "dcache", target_arch = "arm"))]
+    maintain(
+        operation::DCCMVAC,
+        buffer.as_ptr() as usize,
+        core::mem::size_of_val(buffer),
+    );
+    #[cfg(not(all(feature = "dcache", target_arch = "arm")))]
+    let _ = buffer;
+}
+
+/// Discard any cached data for `len` elements at `address`
+///
+/// Use this before the CPU reads data that the DMA channel wrote. The elements
+/// must be part of a buffer prepared by [`receive`], so that the invalidated
+/// lines only hold DMA data.
+pub(crate) fn invalidate<E>(address: *const E, len: usize) {
+    invalidate_bytes(address as usize, len * core::mem::size_of::<E>());
+}
+
+fn invalidate_bytes(address: usize, size: usize) {
+    #[cfg(all(feature = "dcache", target_arch = "arm"))]
+    maintain(operation::DCIMVAC, address, size);
+    #[cfg(not(all(feature = "dcache", target_arch = "arm")))]
+    let _ = (address, size);
+}
+
+/// Prepare `buffer` to receive data from the DMA channel
+///
+/// This writes back and discards any cached data for `buffer`, so that a cache
+/// eviction cannot overwrite data written by the DMA channel. The returned guard
+/// discards the cached data again when it drops. Drop the guard once the DMA
+/// channel stops writing into `buffer`.
+///
+/// # Panics
+///
+/// With the `dcache` feature on an ARM target, panics if `buffer` does not
+/// start and end on a cache line boundary. Otherwise, the buffer could share a
+/// cache line with other data, and maintenance on that line could lose the other
+/// data, or the DMA data.
+pub(crate) fn receive<E>(buffer: &mut [E]) -> Invalidate {
+    let address = buffer.as_ptr() as usize;
+    let size = core::mem::size_of_val(buffer);
+    #[cfg(all(feature = "dcache", target_arch = "arm"))]
+    {
+        assert!(
+            address % LINE_SIZE == 0 && size % LINE_SIZE == 0,
+            "DMA receive buffer is not cache line aligned"
+        );
+        maintain(operation::DCCIMVAC, address, size);
+    }
+    Invalidate { address, size }
+}
+
+/// Discards cached data for a receive buffer when dropped
+///
+/// See [`receive`].
+pub(crate) struct Invalidate {
+    address: usize,
+    size: usize,
+}
+
+impl Drop for Invalidate {
+    fn drop(&mut self) {
+        invalidate_bytes(self.address, self.size);
+    }
+}
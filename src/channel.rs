@@ -8,10 +8,18 @@
 //! enabled.
 
 use crate::{
+    buffer::{Circular, CircularBuffer},
     element::Element,
     ral::{self, dma, dmamux, tcd::BandwidthControl, Static},
     Error,
 };
+use core::{
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+mod typed;
+pub use typed::{Channels, Periodic, TypedChannel};
 
 impl<const CHANNELS: usize> super::Dma<CHANNELS> {
     /// Creates the DMA channel described by `index`.
@@ -29,11 +37,89 @@ impl<const CHANNELS: usize> super::Dma<CHANNELS> {
         assert!(index < CHANNELS);
         Channel {
             index,
+            channels: CHANNELS,
             registers: self.controller,
             multiplexer: self.multiplexer,
-            waker: &self.wakers[index],
+            shared: &self.shared[index],
         }
     }
+
+    /// Allocates the DMA channel described by `index`
+    ///
+    /// Returns `None` if `index` is out of range, or if the channel is already
+    /// allocated. The channel remains allocated until you drop the
+    /// [`AllocatedChannel`].
+    ///
+    /// The allocator only tracks the channels that it allocates. It does not know about
+    /// the channels that you create with [`channel`](Self::channel).
+    ///
+    /// ```no_run
+    /// use imxrt_dma::Dma;
+    /// # const DMA_PTR: *const () = core::ptr::null() as _;
+    /// # const DMAMUX_PTR: *const () = core::ptr::null() as  _;
+    ///
+    /// static DMA: Dma<32> = unsafe { Dma::new(DMA_PTR, DMAMUX_PTR) };
+    ///
+    /// let channel_7 = DMA.try_channel(7).unwrap();
+    /// assert!(DMA.try_channel(7).is_none());
+    ///
+    /// drop(channel_7);
+    /// assert!(DMA.try_channel(7).is_some());
+    /// ```
+    pub fn try_channel(&'static self, index: usize) -> Option<AllocatedChannel> {
+        // The allocator's bitmask covers the 32 channels of the DMA controller.
+        if index >= CHANNELS.min(32) {
+            return None;
+        }
+        let mask = 1 << index;
+        if self.allocated.fetch_or(mask, Ordering::Acquire) & mask != 0 {
+            return None;
+        }
+        // Safety: the allocator guarantees that this is the only channel
+        // for this index.
+        Some(unsafe { self.allocated_channel(index) })
+    }
+
+    /// Creates the channel described by `index`, which the caller marked as allocated
+    ///
+    /// # Safety
+    ///
+    /// The caller must have set the channel's bit in the allocator's bitmask.
+    unsafe fn allocated_channel(&'static self, index: usize) -> AllocatedChannel {
+        AllocatedChannel {
+            channel: self.channel(index),
+            allocated: &self.allocated,
+        }
+    }
+
+    /// Allocates any available DMA channel
+    ///
+    /// Returns the available channel with the lowest index, or `None` if all
+    /// channels are allocated. See [`try_channel`](Self::try_channel) for
+    /// more information.
+    pub fn allocate_any(&'static self) -> Option<AllocatedChannel> {
+        (0..CHANNELS).find_map(|index| self.try_channel(index))
+    }
+
+    /// Returns the first pair of channels that share a priority
+    ///
+    /// Channels within the same group must have unique priorities. If two
+    /// channels have the same priority, the DMA controller signals a channel
+    /// priority error when you activate either channel. See
+    /// [`Error::is_channel_priority`](crate::Error::is_channel_priority).
+    ///
+    /// Returns `None` if all channels have unique priorities.
+    pub fn duplicate_priority(&self) -> Option<(usize, usize)> {
+        use dma::DCHPRI::{CHPRI, GRPPRI};
+        const MASK: u8 = CHPRI::mask | GRPPRI::mask;
+
+        (0..CHANNELS).find_map(|first| {
+            let priority = self.controller.DCHPRI[first].read() & MASK;
+            (first + 1..CHANNELS)
+                .find(|&second| self.controller.DCHPRI[second].read() & MASK == priority)
+                .map(|second| (first, second))
+        })
+    }
 }
 
 /// A DMA channel
@@ -46,12 +132,49 @@ impl<const CHANNELS: usize> super::Dma<CHANNELS> {
 pub struct Channel {
     /// Our channel number, expected to be between [0, 32)
     index: usize,
+    /// The number of channels supported by the DMA controller
+    channels: usize,
     /// Reference to the DMA registers
     registers: Static<dma::RegisterBlock>,
     /// Reference to the DMA multiplexer
     multiplexer: Static<dmamux::RegisterBlock>,
-    /// This channel's waker.
-    pub(crate) waker: &'static super::SharedWaker,
+    /// This channel's waker, and state shared with interrupt handlers.
+    pub(crate) shared: &'static crate::interrupt::Shared,
+}
+
+/// A DMA channel allocated by a [`Dma`](crate::Dma)
+///
+/// Use [`try_channel`](crate::Dma::try_channel) or [`allocate_any`](crate::Dma::allocate_any)
+/// to allocate an `AllocatedChannel`. `AllocatedChannel` dereferences to a [`Channel`], so
+/// you can use it wherever you'd use a `Channel`.
+///
+/// Dropping the `AllocatedChannel` disables the channel, and releases it for another
+/// allocation.
+pub struct AllocatedChannel {
+    channel: Channel,
+    /// The allocator's bitmask
+    allocated: &'static AtomicU32,
+}
+
+impl Deref for AllocatedChannel {
+    type Target = Channel;
+    fn deref(&self) -> &Self::Target {
+        &self.channel
+    }
+}
+
+impl DerefMut for AllocatedChannel {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.channel
+    }
+}
+
+impl Drop for AllocatedChannel {
+    fn drop(&mut self) {
+        self.channel.disable();
+        self.allocated
+            .fetch_and(!(1 << self.channel.index), Ordering::Release);
+    }
 }
 
 impl Channel {
@@ -90,6 +213,63 @@ impl Channel {
         ral::modify_reg!(crate::ral::tcd, tcd, CSR, BWC: raw);
     }
 
+    /// Set the channel's arbitration priority
+    ///
+    /// When the DMA controller uses fixed priority arbitration, it services
+    /// the channel with the highest priority first. `15` is the highest priority,
+    /// and `0` is the lowest priority. Channels within a group must have unique
+    /// priorities; use [`duplicate_priority`](crate::Dma::duplicate_priority) to
+    /// check your priorities.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `priority` is greater than 15.
+    pub fn set_priority(&mut self, priority: u8) {
+        use dma::DCHPRI::CHPRI;
+        assert!(priority <= CHPRI::mask, "Invalid DMA channel priority");
+        let dchpri = &self.registers.DCHPRI[self.index];
+        dchpri.write((dchpri.read() & !CHPRI::mask) | (priority << CHPRI::offset));
+    }
+
+    /// Returns the channel's arbitration priority
+    pub fn priority(&self) -> u8 {
+        use dma::DCHPRI::CHPRI;
+        (self.registers.DCHPRI[self.index].read() & CHPRI::mask) >> CHPRI::offset
+    }
+
+    /// Set whether a higher priority channel can preempt this channel
+    ///
+    /// If `true`, this channel can be temporarily suspended while a higher
+    /// priority channel runs. The higher priority channel must be able to preempt
+    /// other channels; see [`set_preempt_ability`](Channel::set_preempt_ability).
+    pub fn set_preemptable(&mut self, preemptable: bool) {
+        use dma::DCHPRI::ECP;
+        let dchpri = &self.registers.DCHPRI[self.index];
+        dchpri.write((dchpri.read() & !ECP::mask) | ((preemptable as u8) << ECP::offset));
+    }
+
+    /// Indicates if a higher priority channel can preempt this channel
+    pub fn is_preemptable(&self) -> bool {
+        use dma::DCHPRI::ECP;
+        self.registers.DCHPRI[self.index].read() & ECP::mask != 0
+    }
+
+    /// Set whether this channel can preempt a lower priority channel
+    ///
+    /// If `true` (the default setting), this channel can suspend a lower priority
+    /// channel that can be preempted. See [`set_preemptable`](Channel::set_preemptable).
+    pub fn set_preempt_ability(&mut self, can_preempt: bool) {
+        use dma::DCHPRI::DPA;
+        let dchpri = &self.registers.DCHPRI[self.index];
+        dchpri.write((dchpri.read() & !DPA::mask) | ((!can_preempt as u8) << DPA::offset));
+    }
+
+    /// Indicates if this channel can preempt a lower priority channel
+    pub fn can_preempt(&self) -> bool {
+        use dma::DCHPRI::DPA;
+        self.registers.DCHPRI[self.index].read() & DPA::mask == 0
+    }
+
     /// Reset the transfer control descriptor owned by the DMA channel
     ///
     /// `reset` should be called during channel initialization to put the
@@ -98,8 +278,52 @@ impl Channel {
         self.tcd().reset();
     }
 
+    /// Load a transfer control descriptor from memory into the DMA channel
+    ///
+    /// This copies all of `tcd` into the channel. If `tcd` is linked to another
+    /// descriptor, the DMA channel performs a scatter-gather transfer. The
+    /// channel's software start is never set by this call; use [`start`](Channel::start)
+    /// to start a memory-to-memory transfer.
+    ///
+    /// Consider using [`chain`](crate::scatter_gather::chain) or
+    /// [`cycle`](crate::scatter_gather::cycle) for scatter-gather transfers.
+    ///
+    /// # Safety
+    ///
+    /// `tcd` describes the memory involved in the transfer. You must ensure that
+    /// this memory, and all linked descriptors, are valid for the lifetime of the
+    /// transfer.
+    pub unsafe fn load_tcd(&mut self, tcd: &crate::scatter_gather::Tcd) {
+        self.write_tcd(tcd);
+    }
+
+    /// Implementation of [`load_tcd`](Channel::load_tcd)
+    ///
+    /// Only use this from a transfer future that exclusively owns the channel, and
+    /// when the channel is disabled.
+    pub(crate) unsafe fn write_tcd(&self, tcd: &crate::scatter_gather::Tcd) {
+        let src = &tcd.0;
+        let dst = self.tcd();
+        // DONE must be clear before we set ESG in the channel's CSR.
+        self.clear_complete();
+        dst.SADDR.write(src.SADDR.read());
+        dst.SOFF.write(src.SOFF.read());
+        dst.DATTR.write(src.DATTR.read());
+        dst.SATTR.write(src.SATTR.read());
+        dst.NBYTES.write(src.NBYTES.read());
+        dst.SLAST.write(src.SLAST.read());
+        dst.DADDR.write(src.DADDR.read());
+        dst.DOFF.write(src.DOFF.read());
+        dst.CITER.write(src.CITER.read());
+        dst.DLAST_SGA.write(src.DLAST_SGA.read());
+        dst.BITER.write(src.BITER.read());
+        // CSR last, since it may enable scatter-gather.
+        let csr = src.CSR.read() & !(ral::tcd::CSR::START::mask | ral::tcd::CSR::DONE::mask);
+        dst.CSR.write(csr);
+    }
+
     /// Returns a handle to this channel's transfer control descriptor
-    fn tcd(&self) -> &crate::ral::tcd::RegisterBlock {
+    pub(crate) fn tcd(&self) -> &crate::ral::tcd::RegisterBlock {
         &self.registers.TCD[self.index]
     }
 
@@ -120,6 +344,15 @@ impl Channel {
         ral::write_reg!(crate::ral::tcd, tcd, SADDR, saddr as u32);
     }
 
+    /// Returns the channel's current source address
+    ///
+    /// The channel updates the source address as it moves data. Use this
+    /// to observe the progress of a transfer.
+    pub fn source_address(&self) -> *const () {
+        let tcd = self.tcd();
+        ral::read_reg!(crate::ral::tcd, tcd, SADDR) as *const ()
+    }
+
     /// Set the source offset *in bytes*
     ///
     /// `offset` could be negative, which would decrement the address.
@@ -151,6 +384,15 @@ impl Channel {
         ral::write_reg!(crate::ral::tcd, tcd, DADDR, daddr as u32);
     }
 
+    /// Returns the channel's current destination address
+    ///
+    /// The channel updates the destination address as it moves data. Use this
+    /// to observe the progress of a transfer.
+    pub fn destination_address(&self) -> *const () {
+        let tcd = self.tcd();
+        ral::read_reg!(crate::ral::tcd, tcd, DADDR) as *const ()
+    }
+
     /// Set the destination offset *in bytes*
     ///
     /// `offset` could be negative, which would decrement the address.
@@ -199,6 +441,8 @@ impl Channel {
 
     /// Set the destination last addrss adjustment *in bytes*
     ///
+    /// This disables any scatter-gather processing in the channel.
+    ///
     /// # Safety
     ///
     /// This could allow the DMA engine to reference an invalid destination address.
@@ -207,6 +451,7 @@ impl Channel {
     /// current transfer completes.
     pub unsafe fn set_destination_last_address_adjustment(&self, adjustment: i32) {
         let tcd = self.tcd();
+        ral::modify_reg!(crate::ral::tcd, tcd, CSR, ESG: 0);
         ral::write_reg!(crate::ral::tcd, tcd, DLAST_SGA, adjustment);
     }
 
@@ -233,15 +478,107 @@ impl Channel {
     /// Describes how many bytes we should transfer for each DMA service request.
     /// Note that `nbytes` of `0` is interpreted as a 4GB transfer.
     ///
+    /// If the channel has a minor loop offset, the maximum number of bytes
+    /// is 2^10 - 1; see [`set_minor_loop_offset`](Channel::set_minor_loop_offset).
+    /// Otherwise, if the DMA controller enables minor loop mapping, the maximum
+    /// number of bytes is 2^30 - 1. See [`max_minor_loop_bytes`](Channel::max_minor_loop_bytes).
+    ///
     /// # Safety
     ///
     /// This might allow the DMA engine to read beyond the source, or write beyond
     /// the destination. Caller must ensure that the number of bytes per minor loop
     /// is valid for the given transfer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `nbytes` exceeds the channel's maximum number of minor loop bytes.
     pub unsafe fn set_minor_loop_bytes(&self, nbytes: u32) {
-        // Immutable write OK. 32-bit store on NBYTES.
         let tcd = self.tcd();
-        ral::write_reg!(crate::ral::tcd, tcd, NBYTES, nbytes);
+        assert!(
+            nbytes <= self.max_minor_loop_bytes(),
+            "Too many DMA minor loop bytes"
+        );
+        if ral::read_reg!(crate::ral::tcd, tcd, NBYTES, SMLOE, DMLOE) != (0, 0) {
+            // Immutable modify OK. The other NBYTES fields are only changed
+            // through a &mut reference.
+            ral::modify_reg!(crate::ral::tcd, tcd, NBYTES, NBYTES_MLOFFYES: nbytes);
+        } else {
+            // Immutable write OK. 32-bit store on NBYTES.
+            ral::write_reg!(crate::ral::tcd, tcd, NBYTES, nbytes);
+        }
+    }
+
+    /// Set a minor loop offset *in bytes*
+    ///
+    /// When `Some(offset)`, the DMA engine adds the offset to the source and / or
+    /// destination address each time it completes a minor loop. Use this for strided
+    /// and two-dimensional transfers. `None` disables the minor loop offset.
+    ///
+    /// Minor loop offsets require minor loop mapping. Enable minor loop mapping for
+    /// the DMA controller with [`minor_loop_mapping`](crate::controller::Configuration::minor_loop_mapping)
+    /// before you set an offset.
+    ///
+    /// The offset shares space with the minor loop bytes. When the offset is enabled, the
+    /// maximum number of minor loop bytes is 2^10 - 1. Call
+    /// [`set_minor_loop_bytes`](Channel::set_minor_loop_bytes) *after* setting the
+    /// minor loop offset.
+    ///
+    /// # Safety
+    ///
+    /// This may allow the DMA engine to read beyond the source, or write beyond the
+    /// destination. You must ensure that the offset is valid for the transfer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the offset cannot be represented in 20 bits, or if the DMA controller
+    /// doesn't enable minor loop mapping.
+    pub unsafe fn set_minor_loop_offset(&mut self, offset: Option<MinorLoopOffset>) {
+        let tcd = self.tcd();
+        let nbytes = ral::read_reg!(crate::ral::tcd, tcd, NBYTES, NBYTES_MLOFFYES);
+        match offset {
+            Some(offset) => {
+                let (smloe, dmloe, mloff) = match offset {
+                    MinorLoopOffset::Source(mloff) => (1, 0, mloff),
+                    MinorLoopOffset::Destination(mloff) => (0, 1, mloff),
+                    MinorLoopOffset::Both(mloff) => (1, 1, mloff),
+                };
+                assert!(
+                    (-(1 << 19)..(1 << 19)).contains(&mloff),
+                    "DMA minor loop offset exceeds 20 bits"
+                );
+                let registers = &*self.registers;
+                assert!(
+                    ral::read_reg!(crate::ral::dma, registers, CR, EMLM == 1),
+                    "DMA minor loop offset requires minor loop mapping"
+                );
+                ral::write_reg!(
+                    crate::ral::tcd,
+                    tcd,
+                    NBYTES,
+                    SMLOE: smloe,
+                    DMLOE: dmloe,
+                    MLOFF: mloff as u32,
+                    NBYTES_MLOFFYES: nbytes
+                );
+            }
+            None => ral::write_reg!(crate::ral::tcd, tcd, NBYTES, NBYTES_MLOFFYES: nbytes),
+        }
+    }
+
+    /// Returns the minor loop offset for this channel
+    ///
+    /// Returns `None` if the channel doesn't use a minor loop offset.
+    pub fn minor_loop_offset(&self) -> Option<MinorLoopOffset> {
+        let tcd = self.tcd();
+        let (smloe, dmloe) = ral::read_reg!(crate::ral::tcd, tcd, NBYTES, SMLOE, DMLOE);
+        // Sign-extend the 20 bit offset.
+        let mloff = ((ral::read_reg!(crate::ral::tcd, tcd, NBYTES, MLOFF) << 12) as i32) >> 12;
+        match (smloe, dmloe) {
+            (0, 0) => None,
+            (1, 0) => Some(MinorLoopOffset::Source(mloff)),
+            (0, 1) => Some(MinorLoopOffset::Destination(mloff)),
+            _ => Some(MinorLoopOffset::Both(mloff)),
+        }
     }
 
     /// Tells the DMA channel how many transfer iterations to perform
@@ -249,20 +586,70 @@ impl Channel {
     /// A 'transfer iteration' is a read from a source, and a write to a destination, with
     /// read and write sizes described by a minor loop. Each iteration requires a DMA
     /// service request, either from hardware or from software. The maximum number of iterations
-    /// is 2^15.
+    /// is 2^15 - 1. If minor loop linking is enabled, the maximum number of iterations is
+    /// 2^9 - 1; see [`set_minor_loop_link`](Channel::set_minor_loop_link).
     ///
     /// # Safety
     ///
     /// This may allow the DMA engine to read beyond the source, or write beyond
     /// the destination. Caller must ensure that the number of iterations is valid
     /// for the transfer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if minor loop linking is enabled, and `iterations` exceeds 2^9 - 1.
     pub unsafe fn set_transfer_iterations(&mut self, iterations: u16) {
+        self.write_transfer_iterations(iterations);
+    }
+
+    /// Implementation of [`set_transfer_iterations`](Channel::set_transfer_iterations)
+    ///
+    /// Only use this from a transfer future that exclusively owns the channel, and
+    /// when the channel is inactive.
+    pub(crate) unsafe fn write_transfer_iterations(&self, iterations: u16) {
+        let tcd = self.tcd();
+        // Preserve the ELINK bit, and the link channel (if enabled). The ELINK
+        // bit determines how many bits are available for iterations.
+        if ral::read_reg!(crate::ral::tcd, tcd, BITER, ELINK == 1) {
+            assert!(
+                iterations <= ral::tcd::BITER::BITER_ELINKYES::mask,
+                "Too many DMA transfer iterations for minor loop linking"
+            );
+            ral::modify_reg!(crate::ral::tcd, tcd, CITER, CITER_ELINKYES: iterations);
+            ral::modify_reg!(crate::ral::tcd, tcd, BITER, BITER_ELINKYES: iterations);
+        } else {
+            ral::modify_reg!(crate::ral::tcd, tcd, CITER, CITER: iterations);
+            ral::modify_reg!(crate::ral::tcd, tcd, BITER, BITER: iterations);
+        }
+    }
+
+    /// Returns the maximum number of transfer iterations for the channel
+    ///
+    /// The maximum depends on the channel's [minor loop link](Channel::set_minor_loop_link).
+    pub fn max_transfer_iterations(&self) -> u16 {
+        let tcd = self.tcd();
+        if ral::read_reg!(crate::ral::tcd, tcd, BITER, ELINK == 1) {
+            ral::tcd::BITER::BITER_ELINKYES::mask
+        } else {
+            ral::tcd::BITER::BITER::mask
+        }
+    }
+
+    /// Returns the maximum number of minor loop bytes for the channel
+    ///
+    /// The maximum depends on the channel's [minor loop offset](Channel::set_minor_loop_offset),
+    /// and on the DMA controller's minor loop mapping.
+    pub fn max_minor_loop_bytes(&self) -> u32 {
+        use ral::tcd::NBYTES;
         let tcd = self.tcd();
-        // Note that this is clearing the ELINK bit. We don't have support
-        // for channel-to-channel linking right now. Clearing ELINK is intentional
-        // to use the whole 15 bits for iterations.
-        ral::modify_reg!(crate::ral::tcd, tcd, CITER, CITER: iterations);
-        ral::modify_reg!(crate::ral::tcd, tcd, BITER, BITER: iterations);
+        let registers = &*self.registers;
+        if !ral::read_reg!(crate::ral::dma, registers, CR, EMLM == 1) {
+            u32::MAX
+        } else if ral::read_reg!(crate::ral::tcd, tcd, NBYTES, SMLOE, DMLOE) != (0, 0) {
+            NBYTES::NBYTES_MLOFFYES::mask
+        } else {
+            !(NBYTES::SMLOE::mask | NBYTES::DMLOE::mask)
+        }
     }
 
     /// Returns the beginning transfer iterations setting for the channel.
@@ -270,7 +657,98 @@ impl Channel {
     /// This reflects the last call to `set_transfer_iterations`.
     pub fn beginning_transfer_iterations(&self) -> u16 {
         let tcd = self.tcd();
-        ral::read_reg!(crate::ral::tcd, tcd, BITER, BITER)
+        if ral::read_reg!(crate::ral::tcd, tcd, BITER, ELINK == 1) {
+            ral::read_reg!(crate::ral::tcd, tcd, BITER, BITER_ELINKYES)
+        } else {
+            ral::read_reg!(crate::ral::tcd, tcd, BITER, BITER)
+        }
+    }
+
+    /// Returns the current transfer iterations for the channel
+    ///
+    /// This counts down from the [beginning transfer iterations](Channel::beginning_transfer_iterations)
+    /// as the channel completes minor loops. When the channel completes its major loop, the
+    /// current transfer iterations reloads to the beginning transfer iterations.
+    pub fn current_transfer_iterations(&self) -> u16 {
+        let tcd = self.tcd();
+        if ral::read_reg!(crate::ral::tcd, tcd, CITER, ELINK == 1) {
+            ral::read_reg!(crate::ral::tcd, tcd, CITER, CITER_ELINKYES)
+        } else {
+            ral::read_reg!(crate::ral::tcd, tcd, CITER, CITER)
+        }
+    }
+
+    /// Link this channel to another channel on minor loop completion
+    ///
+    /// When `Some(link)`, this channel requests service from the `link` channel
+    /// each time it completes a minor loop. The final minor loop does not
+    /// link; use [`set_major_loop_link`](Channel::set_major_loop_link) for that
+    /// link. `None` disables minor loop linking.
+    ///
+    /// The link channel shares space with the transfer iterations. When minor loop
+    /// linking is enabled, the maximum number of transfer iterations is reduced to
+    /// 2^9 - 1. Call [`set_transfer_iterations`](Channel::set_transfer_iterations)
+    /// *after* setting the minor loop link.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `link` is not a valid channel number. Panics if you enable linking
+    /// while the channel's transfer iterations exceed 2^9 - 1.
+    pub fn set_minor_loop_link(&mut self, link: Option<usize>) {
+        let tcd = self.tcd();
+        let (elink, linkch) = match link {
+            Some(link) => {
+                assert!(link < self.channels, "Invalid DMA minor loop link channel");
+                // The link channel would overwrite the upper iteration bits.
+                let max = ral::tcd::BITER::BITER_ELINKYES::mask;
+                assert!(
+                    self.beginning_transfer_iterations() <= max
+                        && self.current_transfer_iterations() <= max,
+                    "Too many DMA transfer iterations for minor loop linking"
+                );
+                (1, link as u16)
+            }
+            None => (0, 0),
+        };
+        ral::modify_reg!(crate::ral::tcd, tcd, CITER, ELINK: elink, LINKCH: linkch);
+        ral::modify_reg!(crate::ral::tcd, tcd, BITER, ELINK: elink, LINKCH: linkch);
+    }
+
+    /// Returns the channel that's linked on minor loop completion
+    ///
+    /// Returns `None` if minor loop linking is disabled.
+    pub fn minor_loop_link(&self) -> Option<usize> {
+        let tcd = self.tcd();
+        ral::read_reg!(crate::ral::tcd, tcd, BITER, ELINK == 1)
+            .then(|| ral::read_reg!(crate::ral::tcd, tcd, BITER, LINKCH) as usize)
+    }
+
+    /// Link this channel to another channel on major loop completion
+    ///
+    /// When `Some(link)`, this channel requests service from the `link` channel
+    /// when it completes its major loop. `None` disables major loop linking.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `link` is not a valid channel number.
+    pub fn set_major_loop_link(&mut self, link: Option<usize>) {
+        let tcd = self.tcd();
+        match link {
+            Some(link) => {
+                assert!(link < self.channels, "Invalid DMA major loop link channel");
+                ral::modify_reg!(crate::ral::tcd, tcd, CSR, MAJORELINK: 1, MAJORLINKCH: link as u16);
+            }
+            None => ral::modify_reg!(crate::ral::tcd, tcd, CSR, MAJORELINK: 0, MAJORLINKCH: 0),
+        }
+    }
+
+    /// Returns the channel that's linked on major loop completion
+    ///
+    /// Returns `None` if major loop linking is disabled.
+    pub fn major_loop_link(&self) -> Option<usize> {
+        let tcd = self.tcd();
+        ral::read_reg!(crate::ral::tcd, tcd, CSR, MAJORELINK == 1)
+            .then(|| ral::read_reg!(crate::ral::tcd, tcd, CSR, MAJORLINKCH) as usize)
     }
 
     /// Set the DMAMUX channel configuration
@@ -317,6 +795,32 @@ impl Channel {
         self.registers.CERQ.write(self.index as u8);
     }
 
+    /// Cancel the channel's transfer
+    ///
+    /// `cancel` disables the channel, so that it accepts no more service requests,
+    /// and discards any pending software start. If the channel is executing a minor
+    /// loop, `cancel` waits for the minor loop to finish. `cancel` returns once the
+    /// channel is inactive. After `cancel` returns, the DMA engine no longer accesses
+    /// the channel's memory.
+    ///
+    /// `cancel` only affects this channel. It doesn't halt the DMA controller, and
+    /// it doesn't use the controller's error cancel, which could stop another
+    /// channel's minor loop.
+    pub fn cancel(&self) {
+        self.disable();
+
+        let tcd = self.tcd();
+        loop {
+            // A software start could still be pending. If the channel
+            // starts anyway, we'll wait for it on the next iteration.
+            ral::modify_reg!(crate::ral::tcd, tcd, CSR, START: 0);
+            if !self.is_active() {
+                break;
+            }
+            while self.is_active() {}
+        }
+    }
+
     /// Returns `true` if this DMA channel generated an interrupt
     pub fn is_interrupt(&self) -> bool {
         self.registers.INT.read() & (1 << self.index) != 0
@@ -345,6 +849,15 @@ impl Channel {
         ral::modify_reg!(crate::ral::tcd, tcd, CSR, INTMAJOR: intr as u16);
     }
 
+    /// Enable or disable interrupt generation when the transfer is half complete
+    ///
+    /// The interrupt fires when the channel completes half of its transfer iterations.
+    /// You're responsible for registering your interrupt handler.
+    pub fn set_interrupt_on_half(&mut self, intr: bool) {
+        let tcd = self.tcd();
+        ral::modify_reg!(crate::ral::tcd, tcd, CSR, INTHALF: intr as u16);
+    }
+
     /// Indicates if the DMA transfer has completed
     pub fn is_complete(&self) -> bool {
         let tcd = self.tcd();
@@ -365,10 +878,37 @@ impl Channel {
     }
 
     /// Clears the error flag
+    ///
+    /// If [`on_error_interrupt`](crate::Dma::on_error_interrupt) disabled the channel's
+    /// error interrupt, this enables the error interrupt again.
     pub fn clear_error(&self) {
         // Immutable write OK. CERR affects a bit in ERR, which is
         // not written to elsewhere.
         self.registers.CERR.write(self.index as u8);
+        // Clear the error before enabling the interrupt, so that the
+        // interrupt doesn't fire again for the same error.
+        if self.shared.error_masked.swap(false, Ordering::AcqRel) {
+            self.registers.SEEI.write(self.index as u8);
+        }
+    }
+
+    /// Enable or disable interrupt generation when the channel has an error
+    ///
+    /// The error interrupt is shared by all DMA channels. You're responsible for
+    /// registering your error interrupt handler. See
+    /// [`on_error_interrupt`](crate::Dma::on_error_interrupt) for more information.
+    pub fn set_interrupt_on_error(&mut self, intr: bool) {
+        self.shared.error_masked.store(false, Ordering::Release);
+        if intr {
+            self.registers.SEEI.write(self.index as u8);
+        } else {
+            self.registers.CEEI.write(self.index as u8);
+        }
+    }
+
+    /// Indicates if the channel generates an interrupt when it has an error
+    pub fn is_interrupt_on_error(&self) -> bool {
+        self.registers.EEI.read() & (1 << self.index) != 0
     }
 
     /// Indicates if this DMA channel is actively transferring data
@@ -411,6 +951,21 @@ impl Channel {
 // them being (mutably) shared.
 unsafe impl Send for Channel {}
 
+/// A minor loop offset *in bytes*
+///
+/// The DMA engine applies the same offset to the source address, the destination
+/// address, or both addresses. See [`set_minor_loop_offset`](Channel::set_minor_loop_offset)
+/// for more information.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MinorLoopOffset {
+    /// Apply the offset to the source address
+    Source(i32),
+    /// Apply the offset to the destination address
+    Destination(i32),
+    /// Apply the offset to both the source and destination addresses
+    Both(i32),
+}
+
 /// DMAMUX channel configuration
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[non_exhaustive]
@@ -472,6 +1027,27 @@ pub unsafe fn set_source_hardware<E: Element>(chan: &mut Channel, hardware_sourc
     chan.set_source_last_address_adjustment(0);
 }
 
+/// Returns the number of bytes in a minor loop that moves whole source elements `S`,
+/// and whole destination elements `D`
+///
+/// The source and destination of a transfer may use different element types. For
+/// example, a transfer may read 32-bit words from a peripheral FIFO, and write bytes
+/// into memory. Configure each side with its own element type, then use this value
+/// as the channel's [minor loop bytes](Channel::set_minor_loop_bytes). Each minor loop
+/// then moves one element of the wider type, and several elements of the narrower
+/// type.
+pub const fn minor_loop_bytes<S: Element, D: Element>() -> u32 {
+    let source = core::mem::size_of::<S>();
+    let destination = core::mem::size_of::<D>();
+    // Element sizes are powers of two, so the larger size is a multiple of the
+    // smaller size.
+    if source > destination {
+        source as u32
+    } else {
+        destination as u32
+    }
+}
+
 /// Set a hardware peripheral as the destination for a DMA transfer
 ///
 /// `hardware_destination` is expected to point at a peripheral register that can
@@ -505,9 +1081,7 @@ pub unsafe fn set_source_linear_buffer<E: Element>(chan: &mut Channel, source: &
     chan.set_source_address(source.as_ptr());
     chan.set_source_offset(core::mem::size_of::<E>() as i16);
     chan.set_source_attributes::<E>(0);
-    chan.set_source_last_address_adjustment(
-        ((source.len() * core::mem::size_of::<E>()) as i32).wrapping_neg(),
-    );
+    chan.set_source_last_address_adjustment((core::mem::size_of_val(source) as i32).wrapping_neg());
 }
 
 /// Set a linear buffer as the destination for a DMA transfer
@@ -524,28 +1098,18 @@ pub unsafe fn set_destination_linear_buffer<E: Element>(chan: &mut Channel, dest
     chan.set_destination_offset(core::mem::size_of::<E>() as i16);
     chan.set_destination_attributes::<E>(0);
     chan.set_destination_last_address_adjustment(
-        ((destination.len() * core::mem::size_of::<E>()) as i32).wrapping_neg(),
-    );
-}
-
-/// Assert properties about the circular buffer
-fn circular_buffer_asserts<E>(buffer: &[E]) {
-    let len = buffer.len();
-    assert!(
-        len.is_power_of_two(),
-        "DMA circular buffer size is not power of two"
-    );
-    let start = buffer.as_ptr();
-    let size = len * core::mem::size_of::<E>();
-    assert!(
-        (start as usize) % size == 0,
-        "DMA circular buffer is not properly aligned"
+        (core::mem::size_of_val(destination) as i32).wrapping_neg(),
     );
 }
 
 /// Compute the circular buffer modulo value
-fn circular_buffer_modulo<E>(buffer: &[E]) -> u32 {
-    31 - (buffer.len() * core::mem::size_of::<E>()).leading_zeros()
+fn circular_buffer_modulo<E, const N: usize>(buffer: &CircularBuffer<E, N>) -> u32
+where
+    E: Element,
+    [E; N]: Circular,
+{
+    // The size is a power of two.
+    core::mem::size_of_val(buffer).trailing_zeros()
 }
 
 /// Set a circular buffer as the source for a DMA transfer
@@ -557,15 +1121,13 @@ fn circular_buffer_modulo<E>(buffer: &[E]) -> u32 {
 ///
 /// Caller must ensure that the source is valid for the lifetime of the transfer,
 /// and for all subsequent transfers performed by this DMA channel with this buffer.
-///
-/// # Panics
-///
-/// Panics if
-///
-/// - the capacity is not a power of two
-/// - the alignment is not a multiple of the buffer's size in bytes
-pub unsafe fn set_source_circular_buffer<E: Element>(chan: &mut Channel, source: &[E]) {
-    circular_buffer_asserts(source);
+pub unsafe fn set_source_circular_buffer<E, const N: usize>(
+    chan: &mut Channel,
+    source: &CircularBuffer<E, N>,
+) where
+    E: Element,
+    [E; N]: Circular,
+{
     let modulo = circular_buffer_modulo(source);
 
     chan.set_source_address(source.as_ptr());
@@ -583,18 +1145,13 @@ pub unsafe fn set_source_circular_buffer<E: Element>(chan: &mut Channel, source:
 ///
 /// Caller must ensure that the destination is valid for the lifetime of the transfer,
 /// and for all subsequent transfers performed by this DMA channel with this buffer.
-///
-/// # Panics
-///
-/// Panics if
-///
-/// - the capacity is not a power of two
-/// - the alignment is not a multiple of the buffer's size in bytes
-pub unsafe fn set_destination_circular_buffer<E: Element>(
+pub unsafe fn set_destination_circular_buffer<E, const N: usize>(
     chan: &mut Channel,
-    destination: &mut [E],
-) {
-    circular_buffer_asserts(destination);
+    destination: &mut CircularBuffer<E, N>,
+) where
+    E: Element,
+    [E; N]: Circular,
+{
     let modulo = circular_buffer_modulo(destination);
 
     chan.set_destination_address(destination.as_ptr());
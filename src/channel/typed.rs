@@ -0,0 +1,139 @@
+//! Channels that carry their index in their type
+
+use super::{AllocatedChannel, Channel, Configuration};
+use core::{ops::Deref, sync::atomic::Ordering};
+
+/// A DMA channel with index `N`
+///
+/// Use [`split`](crate::Dma::split) to acquire `TypedChannel`s. A `TypedChannel`
+/// is an [`AllocatedChannel`] that knows its index at compile time. Use the index
+/// to check, by type, that a channel supports a feature.
+///
+/// `TypedChannel` dereferences to a shared [`Channel`], so you can query the channel.
+/// It doesn't lend out a mutable `Channel`, since that would allow configurations that
+/// the type rules out. To define and run transfers, convert the `TypedChannel` into an
+/// `AllocatedChannel` with [`into_allocated`](TypedChannel::into_allocated). Dropping
+/// the `TypedChannel` releases the channel for another allocation.
+pub struct TypedChannel<const N: usize>(AllocatedChannel);
+
+impl<const N: usize> TypedChannel<N> {
+    /// The channel's index
+    pub const INDEX: usize = N;
+
+    /// Returns the untyped, allocated channel
+    ///
+    /// The allocated channel keeps any configuration that you applied through
+    /// the `TypedChannel`. It no longer carries the channel's index in its type,
+    /// so the compiler doesn't check its configuration. In particular, you can still
+    /// request periodic triggering with
+    /// [`set_channel_configuration`](Channel::set_channel_configuration), which panics
+    /// if the channel doesn't support it.
+    pub fn into_allocated(self) -> AllocatedChannel {
+        self.0
+    }
+}
+
+impl<const N: usize> Deref for TypedChannel<N> {
+    type Target = Channel;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// A channel that supports periodic triggering
+///
+/// Only the first four DMA channels support periodic triggering from PIT timers.
+/// Requiring `Periodic` turns a periodic configuration on any other channel into
+/// a type error, rather than a panic.
+///
+/// The check only covers `TypedChannel`s. Once you call
+/// [`into_allocated`](TypedChannel::into_allocated), a periodic
+/// [`Configuration`] on an unsupported channel panics at runtime, as it does for
+/// any other [`AllocatedChannel`].
+pub trait Periodic {
+    /// Route the DMA `source` to the channel, and schedule transfers with the
+    /// channel's PIT timer
+    fn set_periodic_configuration(&mut self, source: u32);
+}
+
+macro_rules! periodic {
+    ($($n:literal),*) => {
+        $(
+            impl Periodic for TypedChannel<$n> {
+                fn set_periodic_configuration(&mut self, source: u32) {
+                    self.0.set_channel_configuration(Configuration::Enable {
+                        source,
+                        periodic: true,
+                    });
+                }
+            }
+        )*
+    };
+}
+
+periodic!(0, 1, 2, 3);
+
+macro_rules! channels {
+    ($($field:ident: $n:literal),*) => {
+        /// All DMA channels, each typed with its index
+        ///
+        /// Use [`split`](crate::Dma::split) to acquire `Channels`. A field is `None` if
+        /// its index is not less than the number of channels supported by the `Dma`.
+        pub struct Channels {
+            $(
+                #[doc = concat!("DMA channel ", stringify!($n))]
+                pub $field: Option<TypedChannel<$n>>,
+            )*
+        }
+
+        impl<const CHANNELS: usize> crate::Dma<CHANNELS> {
+            /// Allocates all DMA channels, and returns them as typed channels
+            ///
+            /// Returns `None` if any channel is already allocated. This includes
+            /// channels from a previous `split`, and channels from
+            /// [`try_channel`](Self::try_channel) or [`allocate_any`](Self::allocate_any).
+            /// Once you drop a typed channel, you can allocate it again.
+            ///
+            /// ```no_run
+            /// use imxrt_dma::{channel::Periodic, Dma};
+            /// # const DMA_PTR: *const () = core::ptr::null() as _;
+            /// # const DMAMUX_PTR: *const () = core::ptr::null() as  _;
+            ///
+            /// static DMA: Dma<32> = unsafe { Dma::new(DMA_PTR, DMAMUX_PTR) };
+            ///
+            /// let channels = DMA.split().unwrap();
+            /// let mut channel_2 = channels.ch2.unwrap();
+            /// // Only channels 0 through 3 support periodic triggering.
+            /// channel_2.set_periodic_configuration(/* source = */ 5);
+            ///
+            /// // Define the transfer with the allocated channel.
+            /// let mut channel_2 = channel_2.into_allocated();
+            /// channel_2.set_interrupt_on_completion(true);
+            /// ```
+            pub fn split(&'static self) -> Option<Channels> {
+                let mask = if CHANNELS >= 32 {
+                    u32::MAX
+                } else {
+                    (1 << CHANNELS) - 1
+                };
+                self.allocated
+                    .compare_exchange(0, mask, Ordering::Acquire, Ordering::Relaxed)
+                    .ok()?;
+                Some(Channels {
+                    $(
+                        // Safety: we marked all channels as allocated.
+                        $field: ($n < CHANNELS)
+                            .then(|| TypedChannel(unsafe { self.allocated_channel($n) })),
+                    )*
+                })
+            }
+        }
+    };
+}
+
+channels!(
+    ch0: 0, ch1: 1, ch2: 2, ch3: 3, ch4: 4, ch5: 5, ch6: 6, ch7: 7,
+    ch8: 8, ch9: 9, ch10: 10, ch11: 11, ch12: 12, ch13: 13, ch14: 14, ch15: 15,
+    ch16: 16, ch17: 17, ch18: 18, ch19: 19, ch20: 20, ch21: 21, ch22: 22, ch23: 23,
+    ch24: 24, ch25: 25, ch26: 26, ch27: 27, ch28: 28, ch29: 29, ch30: 30, ch31: 31
+);
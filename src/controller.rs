@@ -0,0 +1,157 @@
+//! DMA controller configuration
+//!
+//! `controller` contains the DMA controller [`Configuration`]. The configuration
+//! affects all DMA channels. Use [`Dma`](crate::Dma) to apply the configuration.
+
+use crate::ral;
+
+/// Describes how the DMA controller selects the next channel, or group,
+/// to service
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arbitration {
+    /// Fixed priority arbitration (default setting)
+    ///
+    /// The controller services the channel, or group, with the highest priority.
+    Fixed,
+    /// Round robin arbitration
+    ///
+    /// The controller services channels, or groups, in order, independent of
+    /// their priorities.
+    RoundRobin,
+}
+
+impl Arbitration {
+    const fn raw(self) -> u32 {
+        match self {
+            Arbitration::Fixed => 0,
+            Arbitration::RoundRobin => 1,
+        }
+    }
+    const fn from_raw(raw: u32) -> Self {
+        if raw == 0 {
+            Arbitration::Fixed
+        } else {
+            Arbitration::RoundRobin
+        }
+    }
+}
+
+/// DMA controller configuration
+///
+/// Use [`new`](Configuration::new) to create a configuration with the controller's
+/// reset values. Then, change the fields you need.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Configuration {
+    /// How the controller arbitrates channels within a group
+    pub channel_arbitration: Arbitration,
+    /// How the controller arbitrates groups
+    pub group_arbitration: Arbitration,
+    /// Halt all transfers when any channel has an error
+    ///
+    /// The controller stays halted until the next configuration.
+    pub halt_on_error: bool,
+    /// Enable continuous link mode
+    ///
+    /// When a channel completes a minor loop, and it links to itself, the
+    /// link is serviced without arbitration.
+    pub continuous_link: bool,
+    /// Enable minor loop mapping
+    ///
+    /// Minor loop mapping is required for minor loop offsets. When enabled,
+    /// the maximum number of minor loop bytes for all channels is 2^30 - 1.
+    pub minor_loop_mapping: bool,
+    /// Stall new channel activations when the CPU is in debug mode
+    pub debug_halt: bool,
+}
+
+impl Configuration {
+    /// Returns a configuration that represents the controller's reset state
+    pub const fn new() -> Self {
+        Configuration {
+            channel_arbitration: Arbitration::Fixed,
+            group_arbitration: Arbitration::Fixed,
+            halt_on_error: false,
+            continuous_link: false,
+            minor_loop_mapping: false,
+            debug_halt: false,
+        }
+    }
+}
+
+impl Default for Configuration {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const CHANNELS: usize> super::Dma<CHANNELS> {
+    /// Apply the DMA controller configuration
+    ///
+    /// This also clears any halt caused by [`halt_on_error`](Configuration::halt_on_error).
+    ///
+    /// ```no_run
+    /// use imxrt_dma::{controller::{Arbitration, Configuration}, Dma};
+    /// # static DMA: Dma<32> = unsafe { Dma::new(core::ptr::null(), core::ptr::null()) };
+    ///
+    /// let mut configuration = Configuration::new();
+    /// configuration.channel_arbitration = Arbitration::RoundRobin;
+    /// configuration.halt_on_error = true;
+    ///
+    /// // Safety: no transfers are active.
+    /// unsafe { DMA.set_configuration(&configuration) };
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// The configuration affects all DMA channels. Changing
+    /// [`minor_loop_mapping`](Configuration::minor_loop_mapping) changes how the
+    /// controller interprets every channel's minor loop bytes, which could allow the
+    /// DMA engine to read or write beyond a buffer. You must ensure that no
+    /// transfers are active, and that all channels are valid for the new configuration.
+    pub unsafe fn set_configuration(&self, configuration: &Configuration) {
+        let controller = &*self.controller;
+        // Channels modify CR to cancel transfers. Don't repeat a cancel
+        // that's in progress.
+        critical_section::with(|_| {
+            ral::modify_reg!(
+                crate::ral::dma,
+                controller,
+                CR,
+                ERCA: configuration.channel_arbitration.raw(),
+                ERGA: configuration.group_arbitration.raw(),
+                HOE: configuration.halt_on_error as u32,
+                HALT: 0,
+                CLM: configuration.continuous_link as u32,
+                EMLM: configuration.minor_loop_mapping as u32,
+                EDBG: configuration.debug_halt as u32,
+                CX: 0,
+                ECX: 0
+            )
+        });
+    }
+
+    /// Returns the DMA controller configuration
+    pub fn configuration(&self) -> Configuration {
+        let controller = &*self.controller;
+        let (erca, erga, hoe, clm, emlm, edbg) = ral::read_reg!(
+            crate::ral::dma,
+            controller,
+            CR,
+            ERCA,
+            ERGA,
+            HOE,
+            CLM,
+            EMLM,
+            EDBG
+        );
+        Configuration {
+            channel_arbitration: Arbitration::from_raw(erca),
+            group_arbitration: Arbitration::from_raw(erga),
+            halt_on_error: hoe != 0,
+            continuous_link: clm != 0,
+            minor_loop_mapping: emlm != 0,
+            debug_halt: edbg != 0,
+        }
+    }
+}
@@ -1,7 +1,7 @@
 //! Trait to generalize acceptable DMA transfer elements
 
-/// Describes a transferrable DMA element; basically, an unsigned
-/// integer of any size.
+/// Describes a transferrable DMA element; basically, an integer or
+/// floating-point number of any size, or a [`Burst16`] or [`Burst32`].
 pub trait Element: Copy + private::Sealed {
     /// An identifier describing the data transfer size
     ///
@@ -26,6 +26,54 @@ impl Element for u64 {
     const DATA_TRANSFER_ID: u8 = 3;
 }
 
+impl Element for i8 {
+    const DATA_TRANSFER_ID: u8 = 0;
+}
+
+impl Element for i16 {
+    const DATA_TRANSFER_ID: u8 = 1;
+}
+
+impl Element for i32 {
+    const DATA_TRANSFER_ID: u8 = 2;
+}
+
+impl Element for i64 {
+    const DATA_TRANSFER_ID: u8 = 3;
+}
+
+impl Element for f32 {
+    const DATA_TRANSFER_ID: u8 = 2;
+}
+
+impl Element for f64 {
+    const DATA_TRANSFER_ID: u8 = 3;
+}
+
+/// A 16 byte DMA element
+///
+/// The DMA channel moves a `Burst16` in a single, 16 byte transfer. Use it to move
+/// large buffers in fewer bus transactions. The element is aligned to its size.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C, align(16))]
+pub struct Burst16(pub [u32; 4]);
+
+impl Element for Burst16 {
+    const DATA_TRANSFER_ID: u8 = 4;
+}
+
+/// A 32 byte DMA element
+///
+/// The DMA channel moves a `Burst32` in a single, 32 byte transfer. Use it to move
+/// large buffers in fewer bus transactions. The element is aligned to its size.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C, align(32))]
+pub struct Burst32(pub [u32; 8]);
+
+impl Element for Burst32 {
+    const DATA_TRANSFER_ID: u8 = 5;
+}
+
 mod private {
     pub trait Sealed {}
 
@@ -33,4 +81,12 @@ mod private {
     impl Sealed for u16 {}
     impl Sealed for u32 {}
     impl Sealed for u64 {}
+    impl Sealed for i8 {}
+    impl Sealed for i16 {}
+    impl Sealed for i32 {}
+    impl Sealed for i64 {}
+    impl Sealed for f32 {}
+    impl Sealed for f64 {}
+    impl Sealed for super::Burst16 {}
+    impl Sealed for super::Burst32 {}
 }
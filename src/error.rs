@@ -15,6 +15,15 @@ pub struct Error {
 }
 
 impl Error {
+    /// The error returned by a cancelled transfer future
+    pub(crate) const CANCELLED: Self = Error::new(1 << 31 | 1 << 16);
+    /// The error returned when the DMA channel overwrites data that you
+    /// have not yet read
+    pub(crate) const OVERRUN: Self = Error::new(1 << 31 | 1 << 30);
+    /// The error returned when a transfer doesn't fit in the DMA channel's
+    /// loops
+    pub(crate) const LOOP_CONFIGURATION: Self = Error::new(1 << 31 | 1 << 3);
+
     #[inline(always)]
     pub(crate) const fn new(es: u32) -> Self {
         Error { es }
@@ -35,7 +44,18 @@ impl Error {
     pub const fn is_valid(self) -> bool {
         self.is_bit(31)
     }
+    /// Indicates that the DMA channel overwrote data that you have not yet read
+    ///
+    /// This is not a DMA controller error. A
+    /// [`ReadStream`](crate::peripheral::ReadStream) returns this error when you
+    /// consume data too slowly.
+    #[inline(always)]
+    pub const fn is_overrun(self) -> bool {
+        self.is_bit(30)
+    }
     /// Indicates if the transfer was cancelled
+    ///
+    /// A transfer future that you cancel resolves with this error.
     #[inline(always)]
     pub const fn is_cancelled(self) -> bool {
         self.is_bit(16)
@@ -106,8 +126,9 @@ impl Debug for Error {
 impl Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f,
-             "DMA_ES: VLD {vld} ECX {ecx} GPE {gpe} CPE {cpe} ERRCHN {errchn} SAE {sae} SOE {soe} DAE {dae} DOE {doe} NCE {nce} SGE {sge} SBE {sbe} DBE {dbe}",
+             "DMA_ES: VLD {vld} OVR {ovr} ECX {ecx} GPE {gpe} CPE {cpe} ERRCHN {errchn} SAE {sae} SOE {soe} DAE {dae} DOE {doe} NCE {nce} SGE {sge} SBE {sbe} DBE {dbe}",
              vld = self.is_valid() as u32,
+             ovr = self.is_overrun() as u32,
              ecx = self.is_cancelled() as u32,
              gpe = self.is_group_priority() as u32,
              cpe = self.is_channel_priority() as u32,
@@ -6,18 +6,18 @@ use core::{
     future::Future,
     marker::PhantomPinned,
     pin::Pin,
-    sync::atomic,
+    sync::atomic::{self, AtomicBool, AtomicU32},
     task::{Context, Poll, Waker},
 };
 
-use cortex_m::interrupt::{self, Mutex};
+use critical_section::Mutex;
 
 impl<const CHANNELS: usize> super::Dma<CHANNELS> {
     /// Handle a DMA interrupt
     ///
     /// Checks the interrupt status for the channel identified by `channel`.
-    /// If the channel completed its transfer, `on_interrupt` wakes the channel's
-    /// waker.
+    /// If the channel generated an interrupt, completed its transfer, or has an
+    /// error, `on_interrupt` wakes the channel's waker.
     ///
     /// Consider calling `on_interrupt` in a DMA channel's interrupt handler:
     ///
@@ -49,25 +49,191 @@ impl<const CHANNELS: usize> super::Dma<CHANNELS> {
     #[inline(always)]
     pub unsafe fn on_interrupt(&'static self, channel: usize) {
         let channel = self.channel(channel);
-        if channel.is_interrupt() {
+        let is_interrupt = channel.is_interrupt();
+        if is_interrupt {
             channel.clear_interrupt();
+            channel.shared.count_interrupt();
         }
 
-        if channel.is_complete() | channel.is_error() {
-            interrupt::free(|cs| {
-                let waker = self.wakers[channel.channel()].borrow(cs);
-                let mut waker = waker.borrow_mut();
-                if let Some(waker) = waker.take() {
-                    waker.wake();
-                }
-            });
+        if is_interrupt | channel.is_complete() | channel.is_error() {
+            self.wake(channel.channel());
         }
     }
+
+    /// Handle all pending DMA interrupts
+    ///
+    /// Reads the interrupt and error status of all channels, then wakes the waker of
+    /// each channel that has an interrupt or an error. Unlike [`on_interrupt`](Self::on_interrupt),
+    /// you don't need to know which channels share an interrupt vector.
+    ///
+    /// `on_pending_interrupts` checks channels that don't share the vector of the
+    /// interrupt you're handling. This is OK, but it does more work than
+    /// [`on_shared_interrupt`](Self::on_shared_interrupt).
+    ///
+    /// ```
+    /// use imxrt_dma::Dma;
+    /// static DMA: Dma<32> = // Handle to DMA driver.
+    /// # unsafe { Dma::new(core::ptr::null(), core::ptr::null()) };
+    ///
+    /// // #[cortex_m_rt::interrupt]
+    /// fn DMA7_DMA23() {
+    ///     // Safety: DMA channels are only used by DMA transfer futures.
+    ///     unsafe { DMA.on_pending_interrupts() };
+    /// }
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// This should only be used when all DMA channels with interrupts or errors are
+    /// exclusively referenced by DMA transfer futures. Caller must ensure that
+    /// `on_pending_interrupts` is called in a DMA interrupt handler.
+    #[inline(always)]
+    pub unsafe fn on_pending_interrupts(&'static self) {
+        let controller = &*self.controller;
+        let pending = controller.INT.read() | controller.ERR.read();
+        for channel in (0..CHANNELS).filter(|channel| pending & (1 << channel) != 0) {
+            let channel = self.channel(channel);
+            if channel.is_interrupt() {
+                channel.clear_interrupt();
+                channel.shared.count_interrupt();
+            }
+            self.wake(channel.channel());
+        }
+    }
+
+    /// Handle the DMA interrupt for a shared interrupt vector
+    ///
+    /// On chips with 32 DMA channels, channels `vector` and `vector + 16` share
+    /// an interrupt vector. `on_shared_interrupt` calls [`on_interrupt`](Self::on_interrupt)
+    /// for each channel of the vector that's less than `CHANNELS`.
+    ///
+    /// ```
+    /// use imxrt_dma::Dma;
+    /// static DMA: Dma<32> = // Handle to DMA driver.
+    /// # unsafe { Dma::new(core::ptr::null(), core::ptr::null()) };
+    ///
+    /// // #[cortex_m_rt::interrupt]
+    /// fn DMA7_DMA23() {
+    ///     // Safety: checking channels 7 and 23, which are used
+    ///     // by DMA transfer futures.
+    ///     unsafe { DMA.on_shared_interrupt(7) };
+    /// }
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// See [`on_interrupt`](Self::on_interrupt). Caller must ensure that `on_shared_interrupt`
+    /// is called in the interrupt handler for `vector`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `vector` is greater than or equal to 16, or `CHANNELS`.
+    #[inline(always)]
+    pub unsafe fn on_shared_interrupt(&'static self, vector: usize) {
+        assert!(vector < 16);
+        self.on_interrupt(vector);
+        if vector + 16 < CHANNELS {
+            self.on_interrupt(vector + 16);
+        }
+    }
+
+    /// Handle a DMA error interrupt
+    ///
+    /// Checks the error status of all channels that enable the error interrupt.
+    /// If a channel has an error, `on_error_interrupt` wakes the channel's waker.
+    /// The channel's future observes, and clears, the error when it's next polled.
+    ///
+    /// The error interrupt remains asserted until the error is cleared. So that it
+    /// doesn't starve the future, `on_error_interrupt` disables the error interrupt
+    /// for each channel with an error. When the future clears the error with
+    /// [`clear_error`](crate::channel::Channel::clear_error), the channel enables
+    /// its error interrupt again.
+    ///
+    /// Consider calling `on_error_interrupt` in the DMA error interrupt handler:
+    ///
+    /// ```
+    /// use imxrt_dma::Dma;
+    /// static DMA: Dma<32> = // Handle to DMA driver.
+    /// # unsafe { Dma::new(core::ptr::null(), core::ptr::null()) };
+    ///
+    /// // #[cortex_m_rt::interrupt]
+    /// fn DMA_ERROR() {
+    ///     // Safety: DMA channels are only used by DMA transfer futures.
+    ///     unsafe { DMA.on_error_interrupt() };
+    /// }
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// This should only be used when the DMA channels that enable the error interrupt
+    /// are exclusively referenced by DMA transfer futures. Caller must ensure that
+    /// `on_error_interrupt` is called in the DMA error interrupt handler.
+    #[inline(always)]
+    pub unsafe fn on_error_interrupt(&'static self) {
+        let controller = &*self.controller;
+        let errors = controller.ERR.read() & controller.EEI.read();
+        for channel in (0..CHANNELS).filter(|channel| errors & (1 << channel) != 0) {
+            let channel = self.channel(channel);
+            controller.CEEI.write(channel.channel() as u8);
+            channel
+                .shared
+                .error_masked
+                .store(true, atomic::Ordering::Release);
+            self.wake(channel.channel());
+        }
+    }
+
+    /// Wake the waker for the channel identified by `channel`
+    fn wake(&self, channel: usize) {
+        critical_section::with(|cs| {
+            let waker = self.shared[channel].waker.borrow(cs);
+            let mut waker = waker.borrow_mut();
+            if let Some(waker) = waker.take() {
+                waker.wake();
+            }
+        });
+    }
+}
+
+/// Set (or clear) the waker for the channel
+pub(crate) fn set_waker(channel: &Channel, new_waker: Option<&Waker>) {
+    critical_section::with(|cs| {
+        let waker = channel.shared.waker.borrow(cs);
+        let mut waker = waker.borrow_mut();
+        *waker = new_waker.cloned();
+    });
+}
+
+/// State shared by a channel and the interrupt handlers
+pub(crate) struct Shared {
+    /// The channel's waker
+    waker: Mutex<RefCell<Option<Waker>>>,
+    /// The number of channel interrupts handled by the interrupt handlers
+    interrupts: AtomicU32,
+    /// Set if the error interrupt handler disabled the channel's error interrupt
+    pub(crate) error_masked: AtomicBool,
+}
+
+impl Shared {
+    /// Count a channel interrupt
+    fn count_interrupt(&self) {
+        self.interrupts.fetch_add(1, atomic::Ordering::Release);
+    }
+
+    /// Returns the number of channel interrupts handled so far
+    ///
+    /// The count wraps.
+    pub(crate) fn interrupts(&self) -> u32 {
+        self.interrupts.load(atomic::Ordering::Acquire)
+    }
 }
 
-pub(crate) type SharedWaker = Mutex<RefCell<Option<Waker>>>;
 #[allow(clippy::declare_interior_mutable_const)] // Very convenient, and usage for static init deemed OK in clippy docs
-pub(crate) const NO_WAKER: SharedWaker = Mutex::new(RefCell::new(None));
+pub(crate) const NO_SHARED: Shared = Shared {
+    waker: Mutex::new(RefCell::new(None)),
+    interrupts: AtomicU32::new(0),
+    error_masked: AtomicBool::new(false),
+};
 
 /// The core DMA transfer future
 ///
@@ -75,7 +241,8 @@ pub(crate) const NO_WAKER: SharedWaker = Mutex::new(RefCell::new(None));
 /// initiate a DMA transfer when it is first polled. You may then poll it
 /// to understand when the transfer completes.
 ///
-/// To cancel a transfer, drop the `Transfer`.
+/// To cancel a transfer, drop the `Transfer`, or use [`cancel`](Transfer::cancel). Either
+/// way, cancellation waits for the DMA channel to become inactive.
 ///
 /// If you've enabled DMA interrupts, consider using [`on_interrupt`](crate::Dma::on_interrupt)
 /// to wake an executor when the DMA transfer completes, The interrupt interface assumes that you've
@@ -112,9 +279,21 @@ pub(crate) const NO_WAKER: SharedWaker = Mutex::new(RefCell::new(None));
 /// ```
 pub struct Transfer<'a> {
     channel: &'a Channel,
+    state: State,
     _pinned: PhantomPinned,
 }
 
+/// The state of a [`Transfer`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// The transfer has not yet resolved
+    Pending,
+    /// The transfer resolved after completing
+    Complete,
+    /// The transfer was cancelled
+    Cancelled,
+}
+
 impl<'a> Transfer<'a> {
     /// Create a new `Transfer` that performs the DMA transfer described by `channel`
     ///
@@ -125,19 +304,54 @@ impl<'a> Transfer<'a> {
     pub unsafe fn new(channel: &'a Channel) -> Self {
         Transfer {
             channel,
+            state: State::Pending,
             _pinned: PhantomPinned,
         }
     }
+
+    /// Cancel the transfer
+    ///
+    /// Once cancelled, the transfer resolves with an error that indicates the
+    /// cancellation; see [`Error::is_cancelled`]. See [`Channel::cancel`] for
+    /// more information on cancellation. If the transfer already resolved,
+    /// `cancel` does nothing.
+    pub fn cancel(self: Pin<&mut Self>) {
+        // Safety: data not moved
+        let this = unsafe { self.get_unchecked_mut() };
+        if this.state == State::Pending {
+            this.channel.cancel();
+            this.state = State::Cancelled;
+        }
+    }
+
+    /// Indicates if the transfer resolved after completing
+    pub(crate) fn is_complete(&self) -> bool {
+        self.state == State::Complete
+    }
+
+    /// Prepare a completed transfer to run again
+    ///
+    /// The caller is responsible for redefining the transfer in the channel
+    /// before the next poll.
+    pub(crate) fn restart(self: Pin<&mut Self>) {
+        // Safety: data not moved
+        let this = unsafe { self.get_unchecked_mut() };
+        if this.state == State::Complete {
+            this.state = State::Pending;
+        }
+    }
 }
 
 impl Future for Transfer<'_> {
     type Output = Result<(), Error>;
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        interrupt::free(|cs| {
-            let waker = self.channel.waker.borrow(cs);
-            let mut waker = waker.borrow_mut();
-            *waker = Some(cx.waker().clone());
-        });
+        match self.state {
+            State::Pending => {}
+            State::Complete => return Poll::Ready(Ok(())),
+            State::Cancelled => return Poll::Ready(Err(Error::CANCELLED)),
+        }
+
+        set_waker(self.channel, Some(cx.waker()));
 
         loop {
             // This driver is only expecting to catch synchronous errors
@@ -150,6 +364,8 @@ impl Future for Transfer<'_> {
                 return Poll::Ready(Err(es));
             } else if self.channel.is_complete() {
                 self.channel.clear_complete();
+                // Safety: OK to change the state...
+                unsafe { self.get_unchecked_mut().state = State::Complete };
                 return Poll::Ready(Ok(()));
             } else if self.channel.is_enabled() {
                 return Poll::Pending;
@@ -163,13 +379,13 @@ impl Future for Transfer<'_> {
 
 impl Drop for Transfer<'_> {
     fn drop(&mut self) {
-        self.channel.disable();
+        if self.state == State::Complete {
+            self.channel.disable();
+        } else {
+            self.channel.cancel();
+        }
         self.channel.clear_complete();
         self.channel.clear_error();
-        interrupt::free(|cs| {
-            let waker = self.channel.waker.borrow(cs);
-            let mut waker = waker.borrow_mut();
-            *waker = None;
-        });
+        set_waker(self.channel, None);
     }
 }
@@ -37,14 +37,26 @@
 //! let mut channel = unsafe { DMA.channel(7) };
 //! ```
 //!
+//! To share one `Dma` among drivers without coordinating channel numbers, allocate
+//! channels with [`try_channel`](crate::Dma::try_channel) or
+//! [`allocate_any`](crate::Dma::allocate_any). These methods are safe, and they
+//! release the channel when you drop it.
+//!
 //! Once you have a channel, you can use the higher-level DMA APIs, like
 //!
 //! - [`memcpy`](crate::memcpy::memcpy) for memory copies.
+//! - [`fill`](crate::memcpy::fill) to fill memory with a value, or with a
+//!   repeating pattern.
 //! - [`write`](crate::peripheral::write) to transmit data from memory to
-//! a peripheral.
+//!   a peripheral.
 //! - [`read`](crate::peripheral::read) to receive data from a peripheral.
 //! - [`full_duplex`](crate::peripheral::full_duplex) to read / write with a
-//! peripheral using a single buffer.
+//!   peripheral using a single buffer.
+//! - [`chain`](crate::scatter_gather::chain) to run a sequence of in-memory
+//!   transfer control descriptors.
+//!
+//! The [`owned`] module offers the same transfers with owned buffers.
+//! Use it when you need transfers that stay memory safe, even if you leak them.
 //!
 //! Peripheral transfers depends on a peripheral's DMA support. These are signaled
 //! through various [`peripheral`](crate::peripheral) traits.
@@ -52,6 +64,30 @@
 //! For a lower-level API, use the [`channel`](crate::channel) objects and helper
 //! functions.
 //!
+//! `imxrt-dma` uses the [`critical-section`](https://docs.rs/critical-section) crate
+//! to share wakers with interrupt handlers. Your program must provide a critical
+//! section implementation. On a single-core i.MX RT processor, consider enabling
+//! the `critical-section-single-core` feature of the `cortex-m` crate.
+//!
+//! To configure the DMA controller, including channel arbitration and minor
+//! loop mapping, see the [`controller`] module.
+//!
+//! To test DMA transfers on your development host, enable the `sim` feature. The
+//! `sim` module simulates the DMA controller without hardware.
+//!
+//! If your program enables the Cortex-M7 data cache, enable the `dcache` feature.
+//! The DMA futures then clean each buffer before the DMA channel reads it, and
+//! invalidate each buffer after the DMA channel writes it. A buffer that receives
+//! DMA data must start and end on a 32 byte cache line boundary, so that it shares
+//! no cache line with other data; otherwise, the future panics. The feature does not
+//! affect the lower-level [`channel`] and
+//! [`scatter_gather`] APIs, which leave cache maintenance
+//! to you.
+//!
+//! Only enable `dcache` when you build for a Cortex-M7 core. The feature cannot
+//! tell a Cortex-M7 from a Cortex-M4, since both use the same Rust targets. Don't
+//! enable it for a Cortex-M4 core, like the second core of an i.MX RT 1170.
+//!
 //! ### License
 //!
 //! Licensed under either of
@@ -67,15 +103,23 @@
 
 #![no_std]
 
+mod buffer;
+mod cache;
 pub mod channel;
+pub mod controller;
 mod element;
 mod error;
 mod interrupt;
 pub mod memcpy;
+pub mod owned;
 pub mod peripheral;
 mod ral;
+pub mod scatter_gather;
+#[cfg(feature = "sim")]
+pub mod sim;
 
-pub use element::Element;
+pub use buffer::{Circular, CircularBuffer};
+pub use element::{Burst16, Burst32, Element};
 pub use error::Error;
 pub use interrupt::Transfer;
 pub use ral::tcd::BandwidthControl;
@@ -93,7 +137,10 @@ pub type Result<T> = core::result::Result<T, Error>;
 pub struct Dma<const CHANNELS: usize> {
     controller: ral::Static<ral::dma::RegisterBlock>,
     multiplexer: ral::Static<ral::dmamux::RegisterBlock>,
-    wakers: [SharedWaker; CHANNELS],
+    /// State shared by each channel and the interrupt handlers
+    shared: [Shared; CHANNELS],
+    /// Channels allocated by the channel allocator, one bit per channel
+    allocated: AtomicU32,
 }
 
 // Safety: OK to allocate a DMA driver in a static context.
@@ -123,9 +170,11 @@ impl<const CHANNELS: usize> Dma<CHANNELS> {
         Self {
             controller: ral::Static(controller.cast()),
             multiplexer: ral::Static(multiplexer.cast()),
-            wakers: [NO_WAKER; CHANNELS],
+            shared: [NO_SHARED; CHANNELS],
+            allocated: AtomicU32::new(0),
         }
     }
 }
 
-use interrupt::{SharedWaker, NO_WAKER};
+use core::sync::atomic::AtomicU32;
+use interrupt::{Shared, NO_SHARED};
@@ -1,9 +1,10 @@
-//! DMA-powered memcpy
+//! DMA-powered memcpy and fills
 
 use crate::{
+    cache,
     channel::{self, Channel},
     interrupt::Transfer,
-    Element, Error,
+    ral, Circular, CircularBuffer, Element, Error,
 };
 
 use core::{
@@ -15,19 +16,58 @@ use core::{
 /// A memcpy operation
 ///
 /// `Memcpy` yields when it's moved the minimum amount of elements between two linear
-/// buffers. Use the [`memcpy`](crate::memcpy::memcpy) function to define the transfer.
+/// buffers. Use the [`memcpy`](crate::memcpy::memcpy) function to define the transfer,
+/// or [`memcpy_2d`] for a two-dimensional transfer. Use
+/// [`fill`] or [`fill_pattern`] to
+/// fill a buffer.
 pub struct Memcpy<'a, E> {
     transfer: Transfer<'a>,
     channel: &'a Channel,
+    /// Describes the destination, for progress reporting.
+    destination: *const E,
+    destination_pitch: usize,
+    rows: usize,
+    columns: usize,
+    /// Set if the copy resolves without using the DMA channel.
+    outcome: Option<Result<(), Error>>,
+    /// Set if `poll` starts the channel with software. Otherwise, an
+    /// always-on request runs the copy.
+    software_start: bool,
+    /// Dropped after `transfer`, once the channel stops.
+    _cache: cache::Invalidate,
     _elem: core::marker::PhantomData<(&'a E, &'a mut E)>,
 }
 
+/// Returns the outcome of a single minor loop copy of `bytes`, if the copy
+/// can't use the DMA channel
+///
+/// There's nothing to copy if `bytes` is zero. If `bytes` exceeds the channel's
+/// maximum, the copy fails with a loop configuration error.
+fn check_minor_loop_bytes(channel: &Channel, bytes: usize) -> Option<Result<(), Error>> {
+    if bytes == 0 {
+        Some(Ok(()))
+    } else if bytes > channel.max_minor_loop_bytes() as usize {
+        Some(Err(Error::LOOP_CONFIGURATION))
+    } else {
+        None
+    }
+}
+
 /// Perform a DMA-powered `memcpy` between the `source` and `destination` buffers
 ///
 /// Copies the minimum number of elements between the two buffers. You're responsible
 /// for enabling any interrupts, and calling [`on_interrupt`](crate::Dma::on_interrupt)
 /// if the interrupt fires. Otherwise, you may poll the transfer until completion.
 ///
+/// The DMA channel moves the data with the widest transfer size, up to 32 bytes, that
+/// the buffers' alignment and length allow. The element type only sets the narrowest
+/// transfer size.
+///
+/// The channel copies all elements in a single minor loop. If the copy is larger than
+/// the channel's [maximum minor loop bytes](Channel::max_minor_loop_bytes), the future
+/// resolves with a [loop configuration](Error::is_loop_configuration) error, and nothing
+/// is copied. An empty copy resolves immediately.
+///
 /// # Example
 ///
 /// Transfer 5 `u32`s between a source and destination buffer. The transfer completes when
@@ -60,6 +100,8 @@ pub fn memcpy<'a, E: Element>(
     destination: &'a mut [E],
     channel: &'a mut Channel,
 ) -> Memcpy<'a, E> {
+    cache::clean(source);
+    let cache = cache::receive(destination);
     channel.disable();
 
     channel.set_disable_on_completion(true);
@@ -82,30 +124,434 @@ pub fn memcpy<'a, E: Element>(
     // Transfer all elements in a single major loop
     //
     // Safety: transferring the minimum number of bytes between buffers,
-    // and there's only one major loop to perform the transfer.
+    // and there's only one major loop to perform the transfer. The transfer
+    // size divides both addresses and the number of bytes. We only program
+    // a byte count that fits in the minor loop.
+    let len = source.len().min(destination.len());
+    let bytes = core::mem::size_of::<E>().saturating_mul(len);
+    let size =
+        widest_transfer_size(source.as_ptr() as usize | destination.as_ptr() as usize | bytes);
+    unsafe { channel.set_minor_loop_offset(None) };
+    let outcome = check_minor_loop_bytes(channel, bytes);
+    if outcome.is_none() {
+        unsafe {
+            set_transfer_size(channel, size);
+            channel.set_minor_loop_bytes(bytes as u32);
+            channel.set_transfer_iterations(1);
+        }
+    }
+
+    Memcpy {
+        // Safety: transfer is properly prepared
+        transfer: unsafe { Transfer::new(channel) },
+        channel,
+        destination: destination.as_ptr(),
+        destination_pitch: len,
+        rows: 1,
+        columns: len,
+        outcome,
+        software_start: true,
+        _cache: cache,
+        _elem: core::marker::PhantomData,
+    }
+}
+
+/// Returns the widest transfer size, in bytes, that divides `bits`
+///
+/// `bits` is the bitwise OR of all addresses and byte counts in the transfer.
+fn widest_transfer_size(bits: usize) -> usize {
+    1 << bits.trailing_zeros().min(5)
+}
+
+/// Set the channel's source and destination transfer size to `size` bytes
+///
+/// # Safety
+///
+/// `size` must divide the source and destination addresses, and the minor
+/// loop byte count.
+unsafe fn set_transfer_size(channel: &Channel, size: usize) {
+    channel.set_source_offset(size as i16);
+    channel.set_destination_offset(size as i16);
+    let tcd = channel.tcd();
+    let id = size.trailing_zeros() as u8;
+    ral::modify_reg!(crate::ral::tcd, tcd, SATTR, SIZE: id);
+    ral::modify_reg!(crate::ral::tcd, tcd, DATTR, SIZE: id);
+}
+
+/// Perform a DMA-powered, two-dimensional `memcpy` between the `source` and `destination`
+/// buffers
+///
+/// Copies `rows` × `columns` elements. Each row of `columns` elements is contiguous in
+/// memory. The pitches describe the number of elements between the start of one row,
+/// and the start of the next row. Use this to copy a sprite into a framebuffer, or
+/// to copy a region of a framebuffer.
+///
+/// The DMA engine applies a single minor loop offset to the source, the destination, or
+/// both. This means that at least one of the pitches must equal `columns`, or both pitches
+/// must be equal. If a pitch differs from `columns`, the size of a row must not exceed
+/// 1023 bytes, and the DMA controller must enable
+/// [minor loop mapping](crate::controller::Configuration::minor_loop_mapping). See
+/// [`set_minor_loop_offset`](Channel::set_minor_loop_offset) for more information.
+///
+/// `memcpy_2d` uses an always-on DMAMUX configuration to transfer each row; the future
+/// never starts the channel with software. Once the copy completes, the channel's
+/// addresses return to the start of each buffer. Like [`memcpy`], you're responsible
+/// for enabling any interrupts.
+///
+/// # Panics
+///
+/// Panics if
+///
+/// - `rows` or `columns` is zero, or `rows` exceeds 2^15 - 1.
+/// - a pitch is less than `columns`.
+/// - a buffer is too small for `rows` rows with its pitch, or the pitch is so large that
+///   the buffer extent overflows.
+/// - the pitches cannot be represented with a single minor loop offset, as described above.
+/// - the pitches need a minor loop offset, and the DMA controller doesn't enable minor loop
+///   mapping.
+/// - a row exceeds the channel's [maximum minor loop bytes](Channel::max_minor_loop_bytes).
+///
+/// # Example
+///
+/// Copy a 4 × 2 sprite into the top-left corner of an 8 × 8 framebuffer.
+///
+/// ```no_run
+/// use imxrt_dma::{channel::Channel, memcpy};
+///
+/// # static DMA: imxrt_dma::Dma<32> = unsafe { imxrt_dma::Dma::new(core::ptr::null(), core::ptr::null()) };
+/// # async fn f() -> imxrt_dma::Result<()> {
+/// let mut channel_7: Channel = // DMA channel 7
+///     # unsafe { DMA.channel(7) };
+///
+/// let sprite = [0xFFFFu16; 4 * 2];
+/// let mut framebuffer = [0u16; 8 * 8];
+///
+/// memcpy::memcpy_2d(&sprite, 2, &mut framebuffer, 8, 4, 2, &mut channel_7).await?;
+/// # Ok(()) }
+/// ```
+pub fn memcpy_2d<'a, E: Element>(
+    source: &'a [E],
+    source_pitch: usize,
+    destination: &'a mut [E],
+    destination_pitch: usize,
+    rows: usize,
+    columns: usize,
+    channel: &'a mut Channel,
+) -> Memcpy<'a, E> {
+    assert!(rows > 0 && columns > 0, "Empty 2D memcpy");
+    assert!(rows <= 0x7FFF, "Too many rows for a 2D memcpy");
+    assert!(
+        columns <= source_pitch && columns <= destination_pitch,
+        "2D memcpy pitch is less than the number of columns"
+    );
+    // The number of elements spanned by `rows` rows with `pitch`.
+    let extent = |pitch: usize| {
+        (rows - 1)
+            .checked_mul(pitch)
+            .and_then(|elements| elements.checked_add(columns))
+            .expect("2D memcpy pitch overflows")
+    };
+    assert!(
+        extent(source_pitch) <= source.len(),
+        "2D memcpy source is too small"
+    );
+    assert!(
+        extent(destination_pitch) <= destination.len(),
+        "2D memcpy destination is too small"
+    );
+
+    let size = core::mem::size_of::<E>();
+    // The offset, in bytes, from the end of one row to the start of the next row.
+    let row_offset = |pitch: usize| {
+        (pitch - columns)
+            .checked_mul(size)
+            .and_then(|bytes| i32::try_from(bytes).ok())
+            .filter(|&bytes| bytes < 1 << 19)
+            .expect("2D memcpy pitch exceeds the minor loop offset range")
+    };
+    let offset = match (source_pitch == columns, destination_pitch == columns) {
+        (true, true) => None,
+        (false, true) => Some(channel::MinorLoopOffset::Source(row_offset(source_pitch))),
+        (true, false) => Some(channel::MinorLoopOffset::Destination(row_offset(
+            destination_pitch,
+        ))),
+        (false, false) => {
+            assert_eq!(
+                source_pitch, destination_pitch,
+                "2D memcpy pitches require different minor loop offsets"
+            );
+            Some(channel::MinorLoopOffset::Both(row_offset(source_pitch)))
+        }
+    };
+    let row_bytes = u32::try_from(columns * size).expect("2D memcpy row is too large");
+    // Each row advances the address by one pitch. Rewind to the start of the
+    // buffer after the last row. Addresses wrap at 32 bits.
+    let rewind =
+        |pitch: usize| (rows.wrapping_mul(pitch).wrapping_mul(size) as u32).wrapping_neg() as i32;
+
+    cache::clean(source);
+    let cache = cache::receive(destination);
+    channel.disable();
+
+    channel.set_disable_on_completion(true);
+
+    // Safety: buffers borrowed by `memcpy_2d`, and will be valid while a transfer
+    // is in progress. The asserts above ensure that the rows and pitches stay
+    // within the buffers.
     unsafe {
-        channel.set_minor_loop_bytes(
-            core::mem::size_of::<E>().saturating_mul(source.len().min(destination.len())) as u32,
-        );
-        channel.set_transfer_iterations(1);
+        channel.set_source_address(source.as_ptr());
+        channel.set_source_offset(size as i16);
+        channel.set_source_attributes::<E>(0);
+        channel.set_source_last_address_adjustment(rewind(source_pitch));
+
+        channel.set_destination_address(destination.as_ptr());
+        channel.set_destination_offset(size as i16);
+        channel.set_destination_attributes::<E>(0);
+        channel.set_destination_last_address_adjustment(rewind(destination_pitch));
+
+        channel.set_minor_loop_offset(offset);
+        channel.set_minor_loop_bytes(row_bytes);
+        channel.set_transfer_iterations(rows as u16);
     }
 
+    // Each minor loop transfers one row. An always-on request lets the
+    // DMA engine move through all rows without software intervention.
+    channel.set_channel_configuration(channel::Configuration::AlwaysOn);
+
     Memcpy {
         // Safety: transfer is properly prepared
         transfer: unsafe { Transfer::new(channel) },
         channel,
+        destination: destination.as_ptr(),
+        destination_pitch,
+        rows,
+        columns,
+        outcome: None,
+        software_start: false,
+        _cache: cache,
         _elem: core::marker::PhantomData,
     }
 }
 
+/// Use a DMA channel to write `value` into every element of `destination`
+///
+/// The channel reads `value` with a zero source offset, so the source is a single
+/// element. Like [`memcpy`], the channel fills all elements in a single minor loop,
+/// and you're responsible for enabling any interrupts.
+///
+/// # Example
+///
+/// Clear a framebuffer.
+///
+/// ```no_run
+/// use imxrt_dma::{channel::Channel, memcpy};
+///
+/// # static DMA: imxrt_dma::Dma<32> = unsafe { imxrt_dma::Dma::new(core::ptr::null(), core::ptr::null()) };
+/// # async fn f() -> imxrt_dma::Result<()> {
+/// let mut channel_7: Channel = // DMA channel 7
+///     # unsafe { DMA.channel(7) };
+///
+/// let mut framebuffer = [0xFFFFu16; 320 * 240];
+///
+/// memcpy::fill(&0, &mut framebuffer, &mut channel_7).await?;
+/// # Ok(()) }
+/// ```
+pub fn fill<'a, E: Element>(
+    value: &'a E,
+    destination: &'a mut [E],
+    channel: &'a mut Channel,
+) -> Memcpy<'a, E> {
+    cache::clean(core::slice::from_ref(value));
+    channel.disable();
+
+    // Safety: value borrowed by `fill`, and will be valid while a transfer
+    // is in progress. A zero offset keeps the channel on the value.
+    unsafe {
+        channel.set_source_address(value);
+        channel.set_source_offset(0);
+        channel.set_source_attributes::<E>(0);
+        channel.set_source_last_address_adjustment(0);
+    }
+
+    fill_destination(destination, channel)
+}
+
+/// Use a DMA channel to repeat `pattern` across `destination`
+///
+/// The channel reads the pattern as a circular buffer. The first element of the
+/// destination receives the first element of the pattern. If the destination's
+/// length isn't a multiple of the pattern's length, the last repetition is partial.
+/// Like [`memcpy`], the channel fills all elements in a single minor loop, and you're
+/// responsible for enabling any interrupts.
+///
+/// # Example
+///
+/// Fill a framebuffer with alternating black and white pixels.
+///
+/// ```no_run
+/// use imxrt_dma::{channel::Channel, memcpy, CircularBuffer};
+///
+/// # static DMA: imxrt_dma::Dma<32> = unsafe { imxrt_dma::Dma::new(core::ptr::null(), core::ptr::null()) };
+/// # async fn f() -> imxrt_dma::Result<()> {
+/// let mut channel_7: Channel = // DMA channel 7
+///     # unsafe { DMA.channel(7) };
+///
+/// let pattern = CircularBuffer::new([0x0000u16, 0xFFFF]);
+/// let mut framebuffer = [0u16; 320 * 240];
+///
+/// memcpy::fill_pattern(&pattern, &mut framebuffer, &mut channel_7).await?;
+/// # Ok(()) }
+/// ```
+pub fn fill_pattern<'a, E, const N: usize>(
+    pattern: &'a CircularBuffer<E, N>,
+    destination: &'a mut [E],
+    channel: &'a mut Channel,
+) -> Memcpy<'a, E>
+where
+    E: Element,
+    [E; N]: Circular,
+{
+    cache::clean(pattern);
+    channel.disable();
+
+    // Safety: pattern borrowed by `fill_pattern`, and will be valid while a
+    // transfer is in progress. The circular buffer modulo ensures that we never
+    // exceed the end of the pattern.
+    unsafe { channel::set_source_circular_buffer(channel, pattern) };
+
+    fill_destination(destination, channel)
+}
+
+/// Prepare a fill of `destination` from the channel's source
+///
+/// The caller disables the channel, and configures the source.
+fn fill_destination<'a, E: Element>(
+    destination: &'a mut [E],
+    channel: &'a mut Channel,
+) -> Memcpy<'a, E> {
+    let cache = cache::receive(destination);
+    channel.set_disable_on_completion(true);
+
+    // Safety: buffer borrowed by the fill, and will be valid while a transfer
+    // is in progress.
+    unsafe { channel::set_destination_linear_buffer(channel, destination) };
+
+    channel.set_channel_configuration(channel::Configuration::Off);
+
+    // Fill all elements in a single major loop
+    //
+    // Safety: transferring the number of bytes in the destination, and
+    // there's only one major loop to perform the transfer. We only program
+    // a byte count that fits in the minor loop.
+    let len = destination.len();
+    let bytes = core::mem::size_of::<E>().saturating_mul(len);
+    unsafe { channel.set_minor_loop_offset(None) };
+    let outcome = check_minor_loop_bytes(channel, bytes);
+    if outcome.is_none() {
+        unsafe {
+            channel.set_minor_loop_bytes(bytes as u32);
+            channel.set_transfer_iterations(1);
+        }
+    }
+
+    Memcpy {
+        // Safety: transfer is properly prepared
+        transfer: unsafe { Transfer::new(channel) },
+        channel,
+        destination: destination.as_ptr(),
+        destination_pitch: len,
+        rows: 1,
+        columns: len,
+        outcome,
+        software_start: true,
+        _cache: cache,
+        _elem: core::marker::PhantomData,
+    }
+}
+
+impl<E> Memcpy<'_, E> {
+    /// Cancel the memcpy, and return the number of elements copied
+    ///
+    /// Once cancelled, the future resolves with an error that indicates the
+    /// cancellation; see [`Error::is_cancelled`]. If the future already resolved,
+    /// `cancel` returns the number of elements in the transfer.
+    pub fn cancel(self: Pin<&mut Self>) -> usize {
+        // Safety: data not moved
+        let this = unsafe { self.get_unchecked_mut() };
+        if !this.transfer.is_complete() {
+            // Safety: data not moved
+            unsafe { Pin::new_unchecked(&mut this.transfer) }.cancel();
+        }
+        this.transferred()
+    }
+
+    /// Returns the number of elements copied so far
+    ///
+    /// The channel reports its progress each time it copies a row. A one-dimensional
+    /// [`memcpy`] copies all elements as a single row, so the count jumps from zero
+    /// to the number of elements when the copy completes.
+    pub fn transferred(&self) -> usize {
+        let total = self.rows * self.columns;
+        if self.outcome.is_some() {
+            return 0;
+        } else if self.transfer.is_complete() {
+            return total;
+        }
+
+        // Each minor loop copies one row. A cancel retires a row early, so
+        // use the destination address to find out how much of the last row
+        // was copied.
+        let channel = self.channel;
+        let is_complete = channel.is_complete();
+        let rows = if is_complete {
+            self.rows
+        } else {
+            (channel.beginning_transfer_iterations() - channel.current_transfer_iterations())
+                as usize
+        };
+        if rows == 0 {
+            return 0;
+        }
+
+        let tcd = channel.tcd();
+        let daddr = ral::read_reg!(crate::ral::tcd, tcd, DADDR);
+        let dlast = if is_complete {
+            ral::read_reg!(crate::ral::tcd, tcd, DLAST_SGA)
+        } else {
+            0
+        };
+        let mloff = match channel.minor_loop_offset() {
+            Some(channel::MinorLoopOffset::Destination(mloff))
+            | Some(channel::MinorLoopOffset::Both(mloff)) => mloff,
+            _ => 0,
+        };
+
+        let size = core::mem::size_of::<E>();
+        let last_row = self.destination as usize + (rows - 1) * self.destination_pitch * size;
+        let last_row_bytes = (daddr.wrapping_sub(dlast as u32) as usize)
+            .wrapping_sub(last_row)
+            .wrapping_sub(mloff as usize);
+        let last_row_elements = (last_row_bytes / size).min(self.columns);
+        ((rows - 1) * self.columns + last_row_elements).min(total)
+    }
+
+    /// Returns the number of elements that remain to be copied
+    pub fn remaining(&self) -> usize {
+        self.rows * self.columns - self.transferred()
+    }
+}
+
 impl<E> Future for Memcpy<'_, E> {
     type Output = Result<(), Error>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(outcome) = self.outcome {
+            return Poll::Ready(outcome);
+        }
         // Safety: data not moved
         let transfer = unsafe { self.as_mut().map_unchecked_mut(|this| &mut this.transfer) };
         let poll = transfer.poll(cx);
-        if poll.is_pending() && !self.channel.is_active() {
+        if poll.is_pending() && self.software_start && !self.channel.is_active() {
             self.channel.start();
         }
         poll
@@ -0,0 +1,361 @@
+//! DMA transfers that own their buffers
+//!
+//! The futures in [`memcpy`](mod@crate::memcpy) and [`peripheral`]
+//! borrow their buffers. If you [`forget`](core::mem::forget) one of those futures,
+//! the DMA channel may keep writing into memory that the borrow checker considers
+//! free.
+//!
+//! The futures in this module take ownership of their buffers, and give them back
+//! when the transfer completes, or when you cancel the transfer. The buffers
+//! implement the [`ReadBuffer`] and [`WriteBuffer`] traits from
+//! [`embedded-dma`](https://docs.rs/embedded-dma). These traits require buffers
+//! that stay valid for as long as you own them, like `&'static mut` slices. If you
+//! forget one of these futures, you also forget the buffer, so the DMA channel
+//! never writes into freed memory.
+//!
+//! Each future resolves with the result of the transfer, and the buffers.
+//!
+//! # Example
+//!
+//! Receive bytes from a LPUART peripheral into a static buffer.
+//!
+//! ```no_run
+//! use imxrt_dma::{owned, peripheral, channel::Channel};
+//! # static DMA: imxrt_dma::Dma<32> = unsafe { imxrt_dma::Dma::new(core::ptr::null(), core::ptr::null()) };
+//! # struct X;
+//! # unsafe impl peripheral::Source<u8> for X {
+//! #   fn source_signal(&self) -> u32 { 0 }
+//! #   fn source_address(&self) -> *const u8 { panic!() }
+//! #   fn enable_source(&mut self) { panic!() }
+//! #   fn disable_source(&mut self) { panic!() }
+//! # }
+//!
+//! # async fn f() -> imxrt_dma::Result<()> {
+//! let mut lpuart = // A LPUART peripheral
+//!     # X;
+//! let mut channel_7: Channel = // DMA channel 7
+//!     # unsafe { DMA.channel(7) };
+//!
+//! static mut BUFFER: [u8; 32] = [0; 32];
+//! // Safety: we only take one reference to the buffer.
+//! let buffer: &'static mut [u8; 32] = unsafe { &mut *core::ptr::addr_of_mut!(BUFFER) };
+//!
+//! let (result, buffer) = owned::read(&mut channel_7, &mut lpuart, buffer).await;
+//! result?;
+//! # let _ = buffer;
+//! # Ok(()) }
+//! ```
+
+pub use embedded_dma::{ReadBuffer, WriteBuffer};
+
+use crate::{
+    channel::Channel,
+    memcpy,
+    peripheral::{self, Bidirectional, Destination, Source},
+    Element, Error,
+};
+
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Returns the elements of a buffer given to the DMA channel for reading
+///
+/// # Safety
+///
+/// The returned slice is only valid while the caller owns `buffer`.
+unsafe fn read_slice<'a, B>(buffer: &B) -> &'a [B::Word]
+where
+    B: ReadBuffer,
+{
+    let (ptr, len) = buffer.read_buffer();
+    core::slice::from_raw_parts(ptr, len)
+}
+
+/// Returns the elements of a buffer given to the DMA channel for writing
+///
+/// # Safety
+///
+/// The returned slice is only valid while the caller owns `buffer`.
+unsafe fn write_slice<'a, B>(buffer: &mut B) -> &'a mut [B::Word]
+where
+    B: WriteBuffer,
+{
+    let (ptr, len) = buffer.write_buffer();
+    core::slice::from_raw_parts_mut(ptr, len)
+}
+
+/// A memcpy that owns its buffers
+///
+/// Use [`memcpy`](crate::owned::memcpy()) to create this future.
+pub struct Memcpy<'a, S, D>
+where
+    S: ReadBuffer,
+    S::Word: Element,
+    D: WriteBuffer<Word = S::Word>,
+{
+    /// Dropped before the buffers, so that the channel stops first.
+    memcpy: memcpy::Memcpy<'a, S::Word>,
+    buffers: Option<(S, D)>,
+}
+
+/// Perform a DMA-powered `memcpy` between the owned `source` and `destination`
+///
+/// See [`memcpy::memcpy`](crate::memcpy::memcpy()) for more information. The future
+/// resolves with the result of the copy, and both buffers.
+pub fn memcpy<S, D>(source: S, mut destination: D, channel: &mut Channel) -> Memcpy<'_, S, D>
+where
+    S: ReadBuffer,
+    S::Word: Element,
+    D: WriteBuffer<Word = S::Word>,
+{
+    // Safety: the future owns the buffers, and drops them after the transfer.
+    let memcpy =
+        unsafe { memcpy::memcpy(read_slice(&source), write_slice(&mut destination), channel) };
+    Memcpy {
+        memcpy,
+        buffers: Some((source, destination)),
+    }
+}
+
+impl<S, D> Memcpy<'_, S, D>
+where
+    S: ReadBuffer,
+    S::Word: Element,
+    D: WriteBuffer<Word = S::Word>,
+{
+    /// Cancel the memcpy, and return the number of elements copied
+    ///
+    /// The future then resolves with a cancellation error, and both buffers.
+    pub fn cancel(self: Pin<&mut Self>) -> usize {
+        // Safety: data not moved
+        let this = unsafe { self.get_unchecked_mut() };
+        unsafe { Pin::new_unchecked(&mut this.memcpy) }.cancel()
+    }
+
+    /// Returns the number of elements copied so far
+    pub fn transferred(&self) -> usize {
+        self.memcpy.transferred()
+    }
+}
+
+impl<S, D> Future for Memcpy<'_, S, D>
+where
+    S: ReadBuffer,
+    S::Word: Element,
+    D: WriteBuffer<Word = S::Word>,
+{
+    type Output = (Result<(), Error>, (S, D));
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // Safety: data not moved
+        let this = unsafe { self.get_unchecked_mut() };
+        let memcpy = unsafe { Pin::new_unchecked(&mut this.memcpy) };
+        let result = core::task::ready!(memcpy.poll(cx));
+        let buffers = this.buffers.take().expect("Polled after completion");
+        Poll::Ready((result, buffers))
+    }
+}
+
+/// A peripheral read that owns its buffer
+///
+/// Use [`read`](crate::owned::read()) to create this future.
+pub struct Read<'a, S, B>
+where
+    B: WriteBuffer,
+    B::Word: Element,
+    S: Source<B::Word>,
+{
+    /// Dropped before the buffer, so that the channel stops first.
+    read: peripheral::Read<'a, S, B::Word>,
+    buffer: Option<B>,
+}
+
+/// Use a DMA channel to receive an owned `buffer` of elements from the source
+/// peripheral
+///
+/// See [`peripheral::read`](crate::peripheral::read()) for more information. The
+/// future resolves with the result of the read, and the buffer.
+pub fn read<'a, S, B>(channel: &'a mut Channel, source: &'a mut S, mut buffer: B) -> Read<'a, S, B>
+where
+    B: WriteBuffer,
+    B::Word: Element,
+    S: Source<B::Word>,
+{
+    // Safety: the future owns the buffer, and drops it after the transfer.
+    let read = peripheral::read(channel, source, unsafe { write_slice(&mut buffer) });
+    Read {
+        read,
+        buffer: Some(buffer),
+    }
+}
+
+impl<S, B> Read<'_, S, B>
+where
+    B: WriteBuffer,
+    B::Word: Element,
+    S: Source<B::Word>,
+{
+    /// Cancel the read, and return the number of elements received
+    ///
+    /// The future then resolves with a cancellation error, and the buffer.
+    pub fn cancel(self: Pin<&mut Self>) -> usize {
+        // Safety: data not moved
+        let this = unsafe { self.get_unchecked_mut() };
+        unsafe { Pin::new_unchecked(&mut this.read) }.cancel()
+    }
+
+    /// Returns the number of elements received so far
+    pub fn transferred(&self) -> usize {
+        self.read.transferred()
+    }
+}
+
+impl<S, B> Future for Read<'_, S, B>
+where
+    B: WriteBuffer,
+    B::Word: Element,
+    S: Source<B::Word>,
+{
+    type Output = (Result<(), Error>, B);
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // Safety: data not moved
+        let this = unsafe { self.get_unchecked_mut() };
+        let read = unsafe { Pin::new_unchecked(&mut this.read) };
+        let result = core::task::ready!(read.poll(cx));
+        let buffer = this.buffer.take().expect("Polled after completion");
+        Poll::Ready((result, buffer))
+    }
+}
+
+/// A peripheral write that owns its buffer
+///
+/// Use [`write`](crate::owned::write()) to create this future.
+pub struct Write<'a, D, B>
+where
+    B: ReadBuffer,
+    B::Word: Element,
+    D: Destination<B::Word>,
+{
+    /// Dropped before the buffer, so that the channel stops first.
+    write: peripheral::Write<'a, D, B::Word>,
+    buffer: Option<B>,
+}
+
+/// Use a DMA channel to send an owned `buffer` of elements to the destination
+/// peripheral
+///
+/// See [`peripheral::write`](crate::peripheral::write()) for more information. The
+/// future resolves with the result of the write, and the buffer.
+pub fn write<'a, D, B>(
+    channel: &'a mut Channel,
+    buffer: B,
+    destination: &'a mut D,
+) -> Write<'a, D, B>
+where
+    B: ReadBuffer,
+    B::Word: Element,
+    D: Destination<B::Word>,
+{
+    // Safety: the future owns the buffer, and drops it after the transfer.
+    let write = peripheral::write(channel, unsafe { read_slice(&buffer) }, destination);
+    Write {
+        write,
+        buffer: Some(buffer),
+    }
+}
+
+impl<D, B> Write<'_, D, B>
+where
+    B: ReadBuffer,
+    B::Word: Element,
+    D: Destination<B::Word>,
+{
+    /// Cancel the write, and return the number of elements sent
+    ///
+    /// The future then resolves with a cancellation error, and the buffer.
+    pub fn cancel(self: Pin<&mut Self>) -> usize {
+        // Safety: data not moved
+        let this = unsafe { self.get_unchecked_mut() };
+        unsafe { Pin::new_unchecked(&mut this.write) }.cancel()
+    }
+
+    /// Returns the number of elements sent so far
+    pub fn transferred(&self) -> usize {
+        self.write.transferred()
+    }
+}
+
+impl<D, B> Future for Write<'_, D, B>
+where
+    B: ReadBuffer,
+    B::Word: Element,
+    D: Destination<B::Word>,
+{
+    type Output = (Result<(), Error>, B);
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // Safety: data not moved
+        let this = unsafe { self.get_unchecked_mut() };
+        let write = unsafe { Pin::new_unchecked(&mut this.write) };
+        let result = core::task::ready!(write.poll(cx));
+        let buffer = this.buffer.take().expect("Polled after completion");
+        Poll::Ready((result, buffer))
+    }
+}
+
+/// A full-duplex transfer that owns its buffer
+///
+/// Use [`full_duplex`](crate::owned::full_duplex()) to create this future.
+pub struct FullDuplex<'a, P, B>
+where
+    B: WriteBuffer,
+    B::Word: Element,
+    P: Bidirectional<B::Word>,
+{
+    /// Dropped before the buffer, so that the channels stop first.
+    full_duplex: peripheral::FullDuplex<'a, P, B::Word>,
+    buffer: Option<B>,
+}
+
+/// Perform a full-duplex transfer with a peripheral, using an owned `buffer`
+///
+/// See [`peripheral::full_duplex`](crate::peripheral::full_duplex()) for more
+/// information. The future resolves with the result of the transfer, and the buffer.
+pub fn full_duplex<'a, P, B>(
+    rx_channel: &'a mut Channel,
+    tx_channel: &'a mut Channel,
+    peripheral: &'a mut P,
+    mut buffer: B,
+) -> FullDuplex<'a, P, B>
+where
+    B: WriteBuffer,
+    B::Word: Element + 'a,
+    P: Bidirectional<B::Word>,
+{
+    // Safety: the future owns the buffer, and drops it after the transfer.
+    let full_duplex = peripheral::full_duplex(rx_channel, tx_channel, peripheral, unsafe {
+        write_slice(&mut buffer)
+    });
+    FullDuplex {
+        full_duplex,
+        buffer: Some(buffer),
+    }
+}
+
+impl<P, B> Future for FullDuplex<'_, P, B>
+where
+    B: WriteBuffer,
+    B::Word: Element,
+    P: Bidirectional<B::Word>,
+{
+    type Output = (Result<(), Error>, B);
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // Safety: data not moved
+        let this = unsafe { self.get_unchecked_mut() };
+        let full_duplex = unsafe { Pin::new_unchecked(&mut this.full_duplex) };
+        let result = core::task::ready!(full_duplex.poll(cx));
+        let buffer = this.buffer.take().expect("Polled after completion");
+        Poll::Ready((result, buffer))
+    }
+}
@@ -8,7 +8,10 @@
 //! Otherwise, you can poll the future in a loop.
 
 use super::{
+    cache,
     channel::{self, Channel, Configuration},
+    ral,
+    scatter_gather::Tcd,
     Element, Error, Transfer,
 };
 
@@ -19,6 +22,9 @@ use core::{
     task::{Context, Poll},
 };
 
+mod ring;
+pub use ring::{read_ring, write_ring, ReadRing, RingBuffer, RingFlush, RingRead, WriteRing};
+
 /// A peripheral that can be the source of DMA data
 ///
 /// By 'source,' we mean that it provides data for a DMA transfer.
@@ -105,6 +111,9 @@ where
     channel: &'a Channel,
     source: &'a mut S,
     transfer: Transfer<'a>,
+    segments: Segments,
+    /// Dropped after `transfer`, once the channel stops.
+    _cache: cache::Invalidate,
     _elem: PhantomData<&'a mut E>,
 }
 
@@ -116,7 +125,45 @@ where
     type Output = Result<(), Error>;
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         // Safety: no movement from transfer future...
-        unsafe { self.map_unchecked_mut(|this| &mut this.transfer) }.poll(cx)
+        let this = unsafe { self.get_unchecked_mut() };
+        let transfer = unsafe { Pin::new_unchecked(&mut this.transfer) };
+        this.segments.poll(transfer, this.channel, cx)
+    }
+}
+
+impl<S, E> Read<'_, S, E>
+where
+    S: Source<E>,
+    E: Element,
+{
+    /// Returns the number of elements received so far
+    ///
+    /// Use this to observe the progress of a running transfer. The count
+    /// increases by one as the channel moves each element.
+    pub fn transferred(&self) -> usize {
+        self.segments.transferred(self.channel, &self.transfer)
+    }
+
+    /// Returns the number of elements that remain to be received
+    pub fn remaining(&self) -> usize {
+        self.segments.len - self.transferred()
+    }
+
+    /// Cancel the transfer, and return the number of elements received
+    ///
+    /// Once cancelled, the future resolves with an error that indicates the
+    /// cancellation; see [`Error::is_cancelled`]. If the future already resolved,
+    /// `cancel` returns the number of elements in the buffer.
+    pub fn cancel(self: Pin<&mut Self>) -> usize {
+        // Safety: no movement from transfer future...
+        let this = unsafe { self.get_unchecked_mut() };
+        if !this.transfer.is_complete() {
+            this.source.disable_source();
+            while this.channel.is_hardware_signaling() {}
+            // Safety: no movement from transfer future...
+            unsafe { Pin::new_unchecked(&mut this.transfer) }.cancel();
+        }
+        this.transferred()
     }
 }
 
@@ -132,11 +179,243 @@ where
     }
 }
 
-fn prepare_read<S, E>(channel: &mut Channel, source: &mut S, buffer: &mut [E])
+/// Splits a peripheral transfer into major loops
+///
+/// A buffer may have more elements than the channel's maximum transfer iterations.
+/// The first segment covers any remainder, so that all other segments have the
+/// maximum number of iterations. Each minor loop moves a single element, and the
+/// memory address continues from one segment to the next.
+///
+/// The channel runs the first segment from its own descriptor. All other segments
+/// alternate between two in-memory descriptors. Each descriptor stops the channel
+/// when it completes. While the channel runs a segment, `poll` prepares the next
+/// segment in the free descriptor, then links the channel to that descriptor. The
+/// DMA engine loads the next segment when the current segment completes, so the
+/// channel doesn't idle between segments. If `poll` is too late, the channel stops
+/// after the segment, and `poll` resumes it with the next segment.
+struct Segments {
+    /// Descriptors for all segments after the first
+    tcds: [Tcd; 2],
+    /// Iterations in the first segment
+    first: usize,
+    /// Iterations in all other segments
+    max: usize,
+    /// Number of segments
+    count: usize,
+    /// The last segment that the channel runs without help from software
+    linked: usize,
+    /// Address of the buffer
+    buffer: u32,
+    /// Elements in the whole transfer
+    len: usize,
+}
+
+impl Segments {
+    /// Set the channel's transfer iterations for the first segment of a `len`
+    /// element transfer
+    ///
+    /// # Safety
+    ///
+    /// The channel must be prepared to move `len` elements, one element per
+    /// minor loop. The buffer's last address adjustment must be zero.
+    unsafe fn new(channel: &mut Channel, len: usize) -> Self {
+        let max = channel.max_transfer_iterations() as usize;
+        let first = match len % max {
+            0 => len.min(max),
+            remainder => remainder,
+        };
+        channel.set_transfer_iterations(first as u16);
+        Segments {
+            tcds: [Tcd::new(), Tcd::new()],
+            first,
+            max,
+            count: 1 + (len - first) / max,
+            linked: 0,
+            buffer: buffer_address(channel.tcd()),
+            len,
+        }
+    }
+
+    /// Poll the segment's transfer, and link the next segment while the channel runs
+    fn poll(
+        &mut self,
+        mut transfer: Pin<&mut Transfer>,
+        channel: &Channel,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Error>> {
+        if self.linked == 0 && self.count > 1 {
+            // The channel hasn't started, so there's no race with the DMA engine.
+            self.prepare(1, channel);
+            self.link(1, channel);
+            self.linked = 1;
+        }
+
+        loop {
+            match transfer.as_mut().poll(cx) {
+                Poll::Pending => {}
+                Poll::Ready(Ok(())) => {
+                    // The channel stopped after the last linked segment.
+                    if self.linked + 1 == self.count {
+                        return Poll::Ready(Ok(()));
+                    }
+                    self.resume(channel);
+                    transfer.as_mut().restart();
+                    continue;
+                }
+                poll => return poll,
+            }
+
+            // Until the channel loads the last linked segment, the other
+            // descriptor is still in use.
+            let tcd = channel.tcd();
+            if self.linked + 1 == self.count || ral::read_reg!(crate::ral::tcd, tcd, CSR, ESG == 1)
+            {
+                return Poll::Pending;
+            }
+
+            let next = self.linked + 1;
+            self.prepare(next, channel);
+            self.link(next, channel);
+            if channel.is_enabled() {
+                self.linked = next;
+            } else {
+                // The segment completed before the link, and the channel
+                // stopped. The DMA engine may have used the descriptor's
+                // address as the last address adjustment.
+                self.resume(channel);
+            }
+        }
+    }
+
+    /// Returns the number of iterations in `segment`
+    fn iterations(&self, segment: usize) -> usize {
+        if segment == 0 {
+            self.first
+        } else {
+            self.max
+        }
+    }
+
+    /// Prepare the descriptor for `segment`
+    ///
+    /// The descriptor continues the previous segment, and stops the channel when
+    /// it completes. The first segment is in the channel, which must not have
+    /// started. Other segments are in the other descriptor.
+    fn prepare(&mut self, segment: usize, channel: &Channel) {
+        use ral::tcd::{BITER, CSR};
+
+        let iterations = self.iterations(segment - 1) as u32;
+        let [even, odd] = &mut self.tcds;
+        let (previous, next) = match segment {
+            1 => (channel.tcd(), odd),
+            segment if segment % 2 == 0 => (&odd.0, even),
+            _ => (&even.0, odd),
+        };
+
+        // A linear buffer advances by the minor loop bytes for each iteration.
+        // A peripheral register doesn't move.
+        let nbytes = previous.NBYTES.read();
+        let advance = |offset: i16| if offset == 0 { 0 } else { iterations * nbytes };
+        let mask = if previous.BITER.read() & BITER::ELINK::mask != 0 {
+            BITER::BITER_ELINKYES::mask
+        } else {
+            BITER::BITER::mask
+        };
+        let biter = (previous.BITER.read() & !mask) | self.max as u16;
+
+        let tcd = &next.0;
+        tcd.SADDR.write(
+            previous
+                .SADDR
+                .read()
+                .wrapping_add(advance(previous.SOFF.read())),
+        );
+        tcd.SOFF.write(previous.SOFF.read());
+        tcd.DATTR.write(previous.DATTR.read());
+        tcd.SATTR.write(previous.SATTR.read());
+        tcd.NBYTES.write(nbytes);
+        tcd.SLAST.write(previous.SLAST.read());
+        tcd.DADDR.write(
+            previous
+                .DADDR
+                .read()
+                .wrapping_add(advance(previous.DOFF.read())),
+        );
+        tcd.DOFF.write(previous.DOFF.read());
+        tcd.CITER.write(biter);
+        tcd.DLAST_SGA.write(0);
+        tcd.BITER.write(biter);
+        tcd.CSR
+            .write(previous.CSR.read() & !(CSR::START::mask | CSR::DONE::mask | CSR::ACTIVE::mask));
+        next.set_next(None);
+        cache::clean(core::slice::from_ref(&*next));
+    }
+
+    /// Link the channel's segment to the descriptor for `segment`
+    ///
+    /// The link fails if the channel completes its segment first. Then, the
+    /// channel stops.
+    fn link(&self, segment: usize, channel: &Channel) {
+        let next: *const Tcd = &self.tcds[segment % 2];
+        // Immutable write OK. The transfer future exclusively owns the channel.
+        let tcd = channel.tcd();
+        ral::write_reg!(crate::ral::tcd, tcd, DLAST_SGA, next as i32);
+        ral::modify_reg!(crate::ral::tcd, tcd, CSR, ESG: 1, DREQ: 0);
+    }
+
+    /// Load the next segment into the stopped channel
+    fn resume(&mut self, channel: &Channel) {
+        self.linked += 1;
+        self.prepare(self.linked, channel);
+        // Safety: the channel is disabled. The descriptor continues the transfer
+        // that the caller prepared.
+        unsafe { channel.write_tcd(&self.tcds[self.linked % 2]) };
+    }
+
+    /// Returns the number of elements moved by the transfer
+    ///
+    /// The channel may be active. In that case, this counts the elements moved
+    /// by completed minor loops.
+    fn transferred(&self, channel: &Channel, transfer: &Transfer) -> usize {
+        if transfer.is_complete() {
+            return self.len;
+        }
+        // The buffer address continues across segments, so it counts the
+        // elements in all segments.
+        let tcd = channel.tcd();
+        let nbytes = ral::read_reg!(crate::ral::tcd, tcd, NBYTES);
+        let moved = buffer_address(tcd).wrapping_sub(self.buffer) / nbytes;
+        (moved as usize).min(self.len)
+    }
+}
+
+/// Returns the channel's current buffer address
+///
+/// The buffer is the source if the source moves. Otherwise, it's the destination.
+fn buffer_address(tcd: &ral::tcd::RegisterBlock) -> u32 {
+    if ral::read_reg!(crate::ral::tcd, tcd, SOFF) != 0 {
+        ral::read_reg!(crate::ral::tcd, tcd, SADDR)
+    } else {
+        ral::read_reg!(crate::ral::tcd, tcd, DADDR)
+    }
+}
+
+/// Prepare a read of peripheral elements `P` into a buffer of `E`
+///
+/// Each minor loop moves one element of the wider type.
+fn prepare_read<S, P, E>(channel: &mut Channel, source: &mut S, buffer: &mut [E]) -> Segments
 where
-    S: Source<E>,
+    S: Source<P>,
+    P: Element,
     E: Element,
 {
+    let minor_loop_bytes = channel::minor_loop_bytes::<P, E>();
+    let bytes = core::mem::size_of_val(buffer);
+    assert!(
+        bytes % minor_loop_bytes as usize == 0,
+        "DMA read buffer holds a partial peripheral element"
+    );
+
     channel.disable();
 
     channel.set_disable_on_completion(true);
@@ -145,18 +424,30 @@ where
     // Destination buffer lifetime captured by future. The combination of minor
     // loops and transfer iterations ensure that we do not exceed the end of the
     // destination.
-    unsafe {
+    let segments = unsafe {
         channel::set_source_hardware(channel, source.source_address());
         channel::set_destination_linear_buffer(channel, buffer);
-        channel.set_minor_loop_bytes(core::mem::size_of::<E>() as u32);
-        channel.set_transfer_iterations(buffer.len() as u16);
-    }
+        // Each segment continues where the previous segment ended.
+        channel.set_destination_last_address_adjustment(0);
+        channel.set_minor_loop_offset(None);
+        channel.set_minor_loop_bytes(minor_loop_bytes);
+        Segments::new(channel, bytes / minor_loop_bytes as usize)
+    };
 
     source.enable_source();
+    segments
 }
 
 /// Use a DMA channel to receive a `buffer` of elements from the source peripheral.
 ///
+/// The buffer may be longer than the channel's
+/// [maximum transfer iterations](crate::channel::Channel::max_transfer_iterations).
+/// In that case, the transfer runs as multiple major loops. Each time the future is
+/// polled, it links the channel's current major loop to the next major loop, so that the
+/// channel continues without stopping. Enable the channel's interrupt on completion to
+/// poll the future after each major loop. If the future isn't polled before the major
+/// loop completes, the channel pauses until the next poll.
+///
 /// Consider using a DMA interrupt handler that calls [`on_interrupt()`](crate::Dma::on_interrupt)
 /// to wake the executor when the transfer completes. Otherwise, poll the future.
 ///
@@ -207,16 +498,381 @@ where
     S: Source<E>,
     E: Element,
 {
-    prepare_read(channel, source, buffer);
+    let cache = cache::receive(buffer);
+    let segments = prepare_read::<S, E, E>(channel, source, buffer);
     Read {
         channel,
         // Safety: transfer is correctly defined
         transfer: unsafe { Transfer::new(channel) },
         source,
+        segments,
+        _cache: cache,
+        _elem: PhantomData,
+    }
+}
+
+/// Use a DMA channel to receive peripheral elements of type `P` into a `buffer` of a
+/// different element type
+///
+/// Use this when the peripheral's data register is wider or narrower than the buffer's
+/// elements. Each minor loop moves one element of the wider type. For example, reading
+/// a 32-bit FIFO into a byte buffer unpacks each word into four bytes, in memory order.
+/// Reading a byte register into a buffer of `u32` packs four reads into each word.
+///
+/// When the peripheral's elements are narrower, each DMA request reads the data register
+/// `size_of::<E>() / size_of::<P>()` times. The peripheral must have that many elements
+/// ready when it requests DMA, so set the peripheral's DMA watermark to cover the whole
+/// minor loop. Otherwise, the DMA channel reads from an empty FIFO.
+///
+/// The returned future counts its progress in elements of the wider type. Otherwise,
+/// it behaves like [`read()`].
+///
+/// # Panics
+///
+/// Panics if the buffer's size is not a multiple of the wider element's size.
+///
+/// # Example
+///
+/// Receive 32-bit words from a FIFO into a byte buffer.
+///
+/// ```no_run
+/// use imxrt_dma::{peripheral, channel::Channel};
+/// # static DMA: imxrt_dma::Dma<32> = unsafe { imxrt_dma::Dma::new(core::ptr::null(), core::ptr::null()) };
+/// # struct X;
+/// # unsafe impl peripheral::Source<u32> for X {
+/// #   fn source_signal(&self) -> u32 { 0 }
+/// #   fn source_address(&self) -> *const u32 { panic!() }
+/// #   fn enable_source(&mut self) { panic!() }
+/// #   fn disable_source(&mut self) { panic!() }
+/// # }
+/// # async fn f() -> imxrt_dma::Result<()> {
+/// let mut lpspi = // A LPSPI peripheral, with a 32-bit receive FIFO
+///     # X;
+/// let mut channel_7: Channel = // DMA channel 7
+///     # unsafe { DMA.channel(7) };
+///
+/// let mut buffer = [0u8; 64];
+/// peripheral::read_packed::<_, u32, _>(&mut channel_7, &mut lpspi, &mut buffer).await?;
+/// # Ok(()) }
+/// ```
+pub fn read_packed<'a, S, P, E>(
+    channel: &'a mut Channel,
+    source: &'a mut S,
+    buffer: &'a mut [E],
+) -> Read<'a, S, P>
+where
+    S: Source<P>,
+    P: Element,
+    E: Element,
+{
+    let cache = cache::receive(buffer);
+    let segments = prepare_read(channel, source, buffer);
+    Read {
+        channel,
+        // Safety: transfer is correctly defined
+        transfer: unsafe { Transfer::new(channel) },
+        source,
+        segments,
+        _cache: cache,
+        _elem: PhantomData,
+    }
+}
+
+/// A double-buffered stream of data from hardware
+///
+/// `ReadStream` continuously receives data from a peripheral into a single buffer.
+/// The buffer is split into two halves. While the DMA channel fills one half, you
+/// copy the other half out of the stream. The DMA channel never stops; once it fills
+/// the second half, it starts over with the first half.
+///
+/// Use [`read_stream()`](crate::peripheral::read_stream) to create a stream. Use
+/// [`next_half()`](crate::peripheral::ReadStream::next_half) to wait for, and copy,
+/// the next half of data. Drop the stream to stop the transfer.
+pub struct ReadStream<'a, S, E>
+where
+    S: Source<E>,
+    E: Element,
+{
+    channel: &'a mut Channel,
+    source: &'a mut S,
+    buffer: *const E,
+    len: usize,
+    /// The half that's copied by the next call to `next_half`.
+    half: usize,
+    /// The channel's interrupt count once the DMA channel fills that half.
+    filled: u32,
+    /// The channel's CSR configuration before the stream, restored on drop.
+    csr: u16,
+    /// Dropped after the stream stops the channel.
+    _cache: cache::Invalidate,
+    _elem: PhantomData<&'a mut [E]>,
+}
+
+/// The CSR fields that continuous receivers, like [`ReadStream`] and [`ReadRing`],
+/// change
+const CONTINUOUS_CSR: u16 =
+    ral::tcd::CSR::DREQ::mask | ral::tcd::CSR::INTHALF::mask | ral::tcd::CSR::INTMAJOR::mask;
+
+/// Receive data from a peripheral into two halves of a `buffer`
+///
+/// The stream starts when you first poll the future returned by
+/// [`next_half()`](crate::peripheral::ReadStream::next_half). You should copy
+/// each half of the buffer before the DMA channel finishes the other half. If you're
+/// too slow, the DMA channel overwrites data that you have not yet read, and
+/// `next_half` returns an [overrun](Error::is_overrun) error.
+///
+/// The stream enables the channel's half and completion interrupts. Call
+/// [`on_interrupt()`](crate::Dma::on_interrupt) in your DMA interrupt handler to
+/// wake the executor. The stream counts these interrupts to detect when the DMA
+/// channel laps you. Without the interrupt handler, poll the future; the stream then
+/// only detects an overrun that happens while it copies a half.
+///
+/// # Panics
+///
+/// Panics if the buffer is empty, if the buffer's length is odd, or if the buffer's
+/// length exceeds 2^15 - 1.
+///
+/// # Example
+///
+/// Continuously receive 32 byte blocks from a LPUART peripheral, using a 64 byte buffer.
+///
+/// ```no_run
+/// use imxrt_dma::{peripheral, channel::Channel};
+/// # static DMA: imxrt_dma::Dma<32> = unsafe { imxrt_dma::Dma::new(core::ptr::null(), core::ptr::null()) };
+/// # struct X;
+/// # unsafe impl peripheral::Source<u8> for X {
+/// #   fn source_signal(&self) -> u32 { 0 }
+/// #   fn source_address(&self) -> *const u8 { panic!() }
+/// #   fn enable_source(&mut self) { panic!() }
+/// #   fn disable_source(&mut self) { panic!() }
+/// # }
+/// # fn process(_: &[u8]) {}
+///
+/// // #[cortex_m_rt::interrupt]
+/// fn DMA7() {
+///     // Safety: DMA channel 7 valid and used by a future.
+///     unsafe { DMA.on_interrupt(7) };
+/// }
+///
+/// # async fn f() -> imxrt_dma::Result<()> {
+/// let mut lpuart = // A LPUART peripheral
+///     # X;
+/// let mut channel_7: Channel = // DMA channel 7
+///     # unsafe { DMA.channel(7) };
+/// // TODO unmask interrupts in NVIC!
+///
+/// let mut buffer = [0u8; 64];
+/// let mut stream = peripheral::read_stream(&mut channel_7, &mut lpuart, &mut buffer);
+/// let mut block = [0u8; 32];
+/// loop {
+///     stream.next_half(&mut block).await?;
+///     process(&block);
+/// }
+/// # Ok(()) }
+/// ```
+pub fn read_stream<'a, S, E>(
+    channel: &'a mut Channel,
+    source: &'a mut S,
+    buffer: &'a mut [E],
+) -> ReadStream<'a, S, E>
+where
+    S: Source<E>,
+    E: Element,
+{
+    assert!(
+        !buffer.is_empty() && buffer.len() % 2 == 0 && buffer.len() <= 0x7FFF,
+        "Invalid DMA read stream buffer length"
+    );
+
+    let cache = cache::receive(buffer);
+    channel.disable();
+
+    let csr = {
+        let tcd = channel.tcd();
+        ral::read_reg!(crate::ral::tcd, tcd, CSR) & CONTINUOUS_CSR
+    };
+    // Never disable the channel; the DMA engine restarts at the beginning
+    // of the buffer when it completes the major loop.
+    channel.set_disable_on_completion(false);
+    // One interrupt for each filled half.
+    channel.set_interrupt_on_half(true);
+    channel.set_interrupt_on_completion(true);
+    channel.clear_interrupt();
+    channel.set_channel_configuration(Configuration::enable(source.source_signal()));
+    // Safety: hardware source address must be valid, otherwise impl is unsound.
+    // Destination buffer lifetime captured by the stream. The last address
+    // adjustment of a linear buffer returns the channel to the start of the
+    // buffer, so we never exceed the end of the destination.
+    unsafe {
+        channel::set_source_hardware(channel, source.source_address());
+        channel::set_destination_linear_buffer(channel, buffer);
+        channel.set_minor_loop_offset(None);
+        channel.set_minor_loop_bytes(core::mem::size_of::<E>() as u32);
+        channel.set_transfer_iterations(buffer.len() as u16);
+    }
+
+    source.enable_source();
+
+    let filled = channel.shared.interrupts().wrapping_add(1);
+    ReadStream {
+        channel,
+        source,
+        buffer: buffer.as_ptr(),
+        len: buffer.len(),
+        half: 0,
+        filled,
+        csr,
+        _cache: cache,
         _elem: PhantomData,
     }
 }
 
+impl<'a, S, E> ReadStream<'a, S, E>
+where
+    S: Source<E>,
+    E: Element,
+{
+    /// Wait for the DMA channel to fill the next half of the buffer, then copy that
+    /// half into `destination`
+    ///
+    /// Halves alternate, starting with the first half. If the DMA channel overwrote
+    /// any of the half before the copy finished, the future resolves with an
+    /// [overrun](Error::is_overrun) error. After an overrun, the next call copies the
+    /// other half.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `destination`'s length isn't half of the stream buffer's length.
+    pub fn next_half<'s>(&'s mut self, destination: &'s mut [E]) -> NextHalf<'s, 'a, S, E> {
+        assert_eq!(
+            destination.len(),
+            self.len / 2,
+            "DMA read stream destination is not half of the buffer"
+        );
+        NextHalf {
+            stream: self,
+            destination,
+        }
+    }
+
+    /// Returns the number of elements written in the current major loop
+    fn position(&self) -> usize {
+        // The channel reloads the iterations when it completes the major loop.
+        (self.channel.beginning_transfer_iterations() - self.channel.current_transfer_iterations())
+            as usize
+    }
+
+    /// Returns the number of interrupts since the DMA channel filled the next half
+    ///
+    /// Negative if the DMA channel has not yet filled the next half.
+    fn interrupts_since_filled(&self) -> i32 {
+        self.channel.shared.interrupts().wrapping_sub(self.filled) as i32
+    }
+
+    /// Indicates if the DMA channel is writing `half` of the buffer
+    fn is_writing(&self, half: usize) -> bool {
+        (self.position() >= self.len / 2) == (half == 1)
+    }
+}
+
+impl<S, E> Drop for ReadStream<'_, S, E>
+where
+    S: Source<E>,
+    E: Element,
+{
+    fn drop(&mut self) {
+        self.source.disable_source();
+        while self.channel.is_hardware_signaling() {}
+        self.channel.cancel();
+        self.channel.clear_complete();
+        self.channel.clear_error();
+        crate::interrupt::set_waker(self.channel, None);
+
+        // Don't leave the stream's interrupts for the channel's next user.
+        let tcd = self.channel.tcd();
+        let csr = ral::read_reg!(crate::ral::tcd, tcd, CSR) & !CONTINUOUS_CSR;
+        ral::write_reg!(crate::ral::tcd, tcd, CSR, csr | self.csr);
+    }
+}
+
+/// Waits for, and copies, the next half of a [`ReadStream`]
+///
+/// Use [`next_half()`](crate::peripheral::ReadStream::next_half) to create this future.
+pub struct NextHalf<'s, 'a, S, E>
+where
+    S: Source<E>,
+    E: Element,
+{
+    stream: &'s mut ReadStream<'a, S, E>,
+    destination: &'s mut [E],
+}
+
+impl<S, E> Future for NextHalf<'_, '_, S, E>
+where
+    S: Source<E>,
+    E: Element,
+{
+    type Output = Result<(), Error>;
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let stream = &mut *this.stream;
+        crate::interrupt::set_waker(stream.channel, Some(cx.waker()));
+
+        if stream.channel.is_error() {
+            let es = stream.channel.error_status();
+            stream.channel.clear_error();
+            return Poll::Ready(Err(es));
+        } else if !stream.channel.is_enabled() {
+            core::sync::atomic::fence(core::sync::atomic::Ordering::SeqCst);
+            // Safety: stream is properly prepared, and the buffer is borrowed
+            // for the lifetime of the stream.
+            unsafe { stream.channel.enable() };
+        }
+
+        // The interrupt count is exact, but it's only available with an
+        // interrupt handler. Otherwise, the half is ready once the DMA channel
+        // writes the other half.
+        let half = stream.half;
+        if stream.interrupts_since_filled() < 0 && stream.is_writing(half) {
+            return Poll::Pending;
+        }
+
+        core::sync::atomic::fence(core::sync::atomic::Ordering::SeqCst);
+        let middle = stream.len / 2;
+        // Safety: the half is within the buffer.
+        let start = unsafe { stream.buffer.add(half * middle) };
+        cache::invalidate(start, middle);
+        for (idx, elem) in this.destination.iter_mut().enumerate() {
+            // Safety: the index is within the half. The DMA channel may write the
+            // half while we copy it; we detect that below.
+            *elem = unsafe { start.add(idx).read_volatile() };
+        }
+        core::sync::atomic::fence(core::sync::atomic::Ordering::SeqCst);
+
+        // One more interrupt means that the DMA channel filled the other half,
+        // and started over in this half.
+        let overrun = stream.interrupts_since_filled() > 0 || stream.is_writing(half);
+        let interrupts = stream.channel.shared.interrupts();
+        if overrun {
+            // Skip to the half that the DMA channel is filling.
+            stream.half = (stream.position() >= middle) as usize;
+            stream.filled = interrupts.wrapping_add(1);
+            Poll::Ready(Err(Error::OVERRUN))
+        } else {
+            stream.half ^= 1;
+            // Without an interrupt handler, the count never advances. Don't
+            // expect more interrupts than the DMA channel can generate for the
+            // next half.
+            stream.filled = if (interrupts.wrapping_sub(stream.filled) as i32) < 0 {
+                interrupts.wrapping_add(2)
+            } else {
+                stream.filled.wrapping_add(1)
+            };
+            Poll::Ready(Ok(()))
+        }
+    }
+}
+
 /// A DMA transfer that sends data to hardware
 ///
 /// The future resolves when the device has sent all provided data.
@@ -229,6 +885,7 @@ where
     channel: &'a Channel,
     destination: &'a mut D,
     transfer: Transfer<'a>,
+    segments: Segments,
     _elem: PhantomData<&'a E>,
 }
 
@@ -240,7 +897,45 @@ where
     type Output = Result<(), Error>;
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         // Safety: no movement from transfer future...
-        unsafe { self.map_unchecked_mut(|this| &mut this.transfer) }.poll(cx)
+        let this = unsafe { self.get_unchecked_mut() };
+        let transfer = unsafe { Pin::new_unchecked(&mut this.transfer) };
+        this.segments.poll(transfer, this.channel, cx)
+    }
+}
+
+impl<D, E> Write<'_, D, E>
+where
+    D: Destination<E>,
+    E: Element,
+{
+    /// Returns the number of elements sent so far
+    ///
+    /// Use this to observe the progress of a running transfer. The count
+    /// increases by one as the channel moves each element.
+    pub fn transferred(&self) -> usize {
+        self.segments.transferred(self.channel, &self.transfer)
+    }
+
+    /// Returns the number of elements that remain to be sent
+    pub fn remaining(&self) -> usize {
+        self.segments.len - self.transferred()
+    }
+
+    /// Cancel the transfer, and return the number of elements sent
+    ///
+    /// Once cancelled, the future resolves with an error that indicates the
+    /// cancellation; see [`Error::is_cancelled`]. If the future already resolved,
+    /// `cancel` returns the number of elements in the buffer.
+    pub fn cancel(self: Pin<&mut Self>) -> usize {
+        // Safety: no movement from transfer future...
+        let this = unsafe { self.get_unchecked_mut() };
+        if !this.transfer.is_complete() {
+            this.destination.disable_destination();
+            while this.channel.is_hardware_signaling() {}
+            // Safety: no movement from transfer future...
+            unsafe { Pin::new_unchecked(&mut this.transfer) }.cancel();
+        }
+        this.transferred()
     }
 }
 
@@ -256,11 +951,23 @@ where
     }
 }
 
-fn prepare_write<D, E>(channel: &mut Channel, buffer: &[E], destination: &mut D)
+/// Prepare a write of a buffer of `E` to peripheral elements `P`
+///
+/// Each minor loop moves one element of the wider type.
+fn prepare_write<D, P, E>(channel: &mut Channel, buffer: &[E], destination: &mut D) -> Segments
 where
-    D: Destination<E>,
+    D: Destination<P>,
+    P: Element,
     E: Element,
 {
+    let minor_loop_bytes = channel::minor_loop_bytes::<E, P>();
+    let bytes = core::mem::size_of_val(buffer);
+    assert!(
+        bytes % minor_loop_bytes as usize == 0,
+        "DMA write buffer holds a partial peripheral element"
+    );
+
+    cache::clean(buffer);
     channel.disable();
     channel.set_disable_on_completion(true);
     channel.set_channel_configuration(Configuration::enable(destination.destination_signal()));
@@ -268,18 +975,30 @@ where
     // Source buffer lifetime captured by future. The combination of minor
     // loops and transfer iterations ensure that we do not exceed the end of the
     // source.
-    unsafe {
+    let segments = unsafe {
         channel::set_source_linear_buffer(channel, buffer);
+        // Each segment continues where the previous segment ended.
+        channel.set_source_last_address_adjustment(0);
         channel::set_destination_hardware(channel, destination.destination_address());
-        channel.set_minor_loop_bytes(core::mem::size_of::<E>() as u32);
-        channel.set_transfer_iterations(buffer.len() as u16);
-    }
+        channel.set_minor_loop_offset(None);
+        channel.set_minor_loop_bytes(minor_loop_bytes);
+        Segments::new(channel, bytes / minor_loop_bytes as usize)
+    };
 
     destination.enable_destination();
+    segments
 }
 
 /// Use a DMA channel to send a `buffer` of data to the destination peripheral.
 ///
+/// The buffer may be longer than the channel's
+/// [maximum transfer iterations](crate::channel::Channel::max_transfer_iterations).
+/// In that case, the transfer runs as multiple major loops. Each time the future is
+/// polled, it links the channel's current major loop to the next major loop, so that the
+/// channel continues without stopping. Enable the channel's interrupt on completion to
+/// poll the future after each major loop. If the future isn't polled before the major
+/// loop completes, the channel pauses until the next poll.
+///
 /// Consider using a DMA interrupt handler that calls [`on_interrupt()`](crate::Dma::on_interrupt)
 /// to wake the executor when the transfer completes. Otherwise, poll the future.
 ///
@@ -331,12 +1050,78 @@ where
     D: Destination<E>,
     E: Element,
 {
-    prepare_write(channel, buffer, destination);
+    let segments = prepare_write::<D, E, E>(channel, buffer, destination);
+    Write {
+        channel,
+        destination,
+        // Safety: transfer is correctly defined
+        transfer: unsafe { Transfer::new(channel) },
+        segments,
+        _elem: PhantomData,
+    }
+}
+
+/// Use a DMA channel to send a `buffer` of data to peripheral elements of a different
+/// type `P`
+///
+/// Use this when the peripheral's data register is wider or narrower than the buffer's
+/// elements. Each minor loop moves one element of the wider type. For example, sending a
+/// byte buffer to a 32-bit data register packs four bytes, in memory order, into each
+/// register write.
+///
+/// When the peripheral's elements are narrower, each DMA request writes the data register
+/// `size_of::<E>() / size_of::<P>()` times. The peripheral must have room for that many
+/// elements when it requests DMA, so set the peripheral's DMA watermark to cover the whole
+/// minor loop. Otherwise, the DMA channel overflows the FIFO.
+///
+/// The returned future counts its progress in elements of the wider type. Otherwise,
+/// it behaves like [`write()`].
+///
+/// # Panics
+///
+/// Panics if the buffer's size is not a multiple of the wider element's size.
+///
+/// # Example
+///
+/// Pack bytes into 32-bit writes to a peripheral data register.
+///
+/// ```no_run
+/// use imxrt_dma::{peripheral, channel::Channel};
+/// # static DMA: imxrt_dma::Dma<32> = unsafe { imxrt_dma::Dma::new(core::ptr::null(), core::ptr::null()) };
+/// # struct X;
+/// # unsafe impl peripheral::Destination<u32> for X {
+/// #   fn destination_signal(&self) -> u32 { 0 }
+/// #   fn destination_address(&self) -> *const u32 { panic!() }
+/// #   fn enable_destination(&mut self) { panic!() }
+/// #   fn disable_destination(&mut self) { panic!() }
+/// # }
+/// # async fn f() -> imxrt_dma::Result<()> {
+/// let mut lpspi = // A LPSPI peripheral, with a 32-bit transmit data register
+///     # X;
+/// let mut channel_7: Channel = // DMA channel 7
+///     # unsafe { DMA.channel(7) };
+///
+/// let buffer = [0xAAu8; 64];
+/// peripheral::write_packed::<_, u32, _>(&mut channel_7, &buffer, &mut lpspi).await?;
+/// # Ok(()) }
+/// ```
+pub fn write_packed<'a, D, P, E>(
+    channel: &'a mut Channel,
+    buffer: &'a [E],
+    destination: &'a mut D,
+) -> Write<'a, D, P>
+where
+    D: Destination<P>,
+    P: Element,
+    E: Element,
+{
+    let segments = prepare_write(channel, buffer, destination);
     Write {
         channel,
         destination,
         // Safety: transfer is correctly defined
         transfer: unsafe { Transfer::new(channel) },
+        segments,
         _elem: PhantomData,
     }
 }
@@ -368,11 +1153,15 @@ where
 {
     rx_channel: &'a Channel,
     rx_transfer: Transfer<'a>,
+    rx_segments: Segments,
     rx_done: bool,
     tx_channel: &'a Channel,
     tx_transfer: Transfer<'a>,
+    tx_segments: Segments,
     tx_done: bool,
     peripheral: &'a mut P,
+    /// Dropped after both transfers, once the channels stop.
+    _cache: cache::Invalidate,
     _elem: PhantomData<E>,
 }
 
@@ -447,17 +1236,21 @@ where
     P: Bidirectional<E>,
     E: Element,
 {
-    prepare_write(tx_channel, buffer, peripheral);
-    prepare_read(rx_channel, peripheral, buffer);
+    let tx_segments = prepare_write::<P, E, E>(tx_channel, buffer, peripheral);
+    let cache = cache::receive(buffer);
+    let rx_segments = prepare_read::<P, E, E>(rx_channel, peripheral, buffer);
 
     FullDuplex {
         rx_channel,
         rx_transfer: unsafe { Transfer::new(rx_channel) },
+        rx_segments,
         rx_done: false,
         tx_channel,
         tx_transfer: unsafe { Transfer::new(tx_channel) },
+        tx_segments,
         tx_done: false,
         peripheral,
+        _cache: cache,
         _elem: PhantomData,
     }
 }
@@ -469,30 +1262,25 @@ where
 {
     type Output = Result<(), Error>;
 
-    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        if !self.rx_done {
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // Safety: pin projection OK, no movement from future...
+        let this = unsafe { self.get_unchecked_mut() };
+
+        if !this.rx_done {
             // Safety: pin projection OK, no movement from future...
-            let poll = unsafe {
-                self.as_mut()
-                    .map_unchecked_mut(|this| &mut this.rx_transfer)
-            }
-            .poll(cx)?;
-            // Safety: OK to toggle a bool...
-            *unsafe { &mut self.as_mut().get_unchecked_mut().rx_done } = poll.is_ready();
+            let transfer = unsafe { Pin::new_unchecked(&mut this.rx_transfer) };
+            let poll = this.rx_segments.poll(transfer, this.rx_channel, cx)?;
+            this.rx_done = poll.is_ready();
         }
 
-        if !self.tx_done {
+        if !this.tx_done {
             // Safety: pin projection OK, no movement from future...
-            let poll = unsafe {
-                self.as_mut()
-                    .map_unchecked_mut(|this| &mut this.tx_transfer)
-            }
-            .poll(cx)?;
-            // Safety: OK to toggle a bool...
-            *unsafe { &mut self.as_mut().get_unchecked_mut().tx_done } = poll.is_ready();
+            let transfer = unsafe { Pin::new_unchecked(&mut this.tx_transfer) };
+            let poll = this.tx_segments.poll(transfer, this.tx_channel, cx)?;
+            this.tx_done = poll.is_ready();
         }
 
-        if self.tx_done && self.rx_done {
+        if this.tx_done && this.rx_done {
             Poll::Ready(Ok(()))
         } else {
             Poll::Pending
@@ -0,0 +1,744 @@
+//! Circular buffers that continuously move data to or from a peripheral
+
+use super::{Destination, Source, CONTINUOUS_CSR};
+use crate::{
+    cache,
+    channel::{self, Channel, Configuration},
+    ral, Circular, CircularBuffer, Element, Error,
+};
+
+use core::{
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    sync::atomic,
+    task::{Context, Poll},
+};
+
+/// A buffer that a DMA channel can use as a ring
+///
+/// A [`CircularBuffer`] wraps with the DMA channel's address modulo. It needs a
+/// power-of-two length, and an alignment that matches its size.
+///
+/// Any other array or slice wraps once the DMA channel completes a major loop,
+/// using the channel's last address adjustment. These buffers may have any length
+/// and alignment. A [`WriteRing`] that uses one of these buffers cannot send
+/// elements across the end of the buffer in one transfer; it sends the elements
+/// that follow the wrap in its next transfer.
+///
+/// This trait is sealed.
+pub trait RingBuffer<E: Element>: AsMut<[E]> + private::Sealed {
+    /// `true` if the DMA channel wraps with its address modulo
+    #[doc(hidden)]
+    const MODULO: bool;
+
+    /// Set this buffer as the channel's source
+    ///
+    /// # Safety
+    ///
+    /// Same as [`channel::set_source_linear_buffer`].
+    #[doc(hidden)]
+    unsafe fn set_source(&mut self, channel: &mut Channel);
+
+    /// Set this buffer as the channel's destination
+    ///
+    /// # Safety
+    ///
+    /// Same as [`channel::set_destination_linear_buffer`].
+    #[doc(hidden)]
+    unsafe fn set_destination(&mut self, channel: &mut Channel);
+}
+
+impl<E, const N: usize> RingBuffer<E> for CircularBuffer<E, N>
+where
+    E: Element,
+    [E; N]: Circular,
+{
+    const MODULO: bool = true;
+    unsafe fn set_source(&mut self, channel: &mut Channel) {
+        channel::set_source_circular_buffer(channel, self);
+    }
+    unsafe fn set_destination(&mut self, channel: &mut Channel) {
+        channel::set_destination_circular_buffer(channel, self);
+    }
+}
+
+impl<E: Element> RingBuffer<E> for [E] {
+    const MODULO: bool = false;
+    unsafe fn set_source(&mut self, channel: &mut Channel) {
+        channel::set_source_linear_buffer(channel, self);
+    }
+    unsafe fn set_destination(&mut self, channel: &mut Channel) {
+        channel::set_destination_linear_buffer(channel, self);
+    }
+}
+
+impl<E: Element, const N: usize> RingBuffer<E> for [E; N] {
+    const MODULO: bool = false;
+    unsafe fn set_source(&mut self, channel: &mut Channel) {
+        channel::set_source_linear_buffer(channel, self);
+    }
+    unsafe fn set_destination(&mut self, channel: &mut Channel) {
+        channel::set_destination_linear_buffer(channel, self);
+    }
+}
+
+/// Call `f` with the start and length of each contiguous run of `count` slots,
+/// beginning at slot `start` of a ring with `len` slots
+fn runs(start: usize, count: usize, len: usize, mut f: impl FnMut(usize, usize)) {
+    let first = count.min(len - start);
+    if first > 0 {
+        f(start, first);
+    }
+    if count > first {
+        f(0, count - first);
+    }
+}
+
+mod private {
+    use crate::{Circular, CircularBuffer, Element};
+
+    pub trait Sealed {}
+
+    impl<E, const N: usize> Sealed for CircularBuffer<E, N>
+    where
+        E: Element,
+        [E; N]: Circular,
+    {
+    }
+    impl<E: Element> Sealed for [E] {}
+    impl<E: Element, const N: usize> Sealed for [E; N] {}
+}
+
+/// A circular buffer that continuously receives data from hardware
+///
+/// The DMA channel writes each element from the peripheral into the next slot of
+/// a [`RingBuffer`]. It never stops; once it writes the last slot, it starts over
+/// at the first slot. Meanwhile, you consume elements from the ring with
+/// [`read`](ReadRing::read), [`try_read`](ReadRing::try_read) and
+/// [`peek`](ReadRing::peek).
+///
+/// The ring holds at most one less element than its buffer. If the peripheral
+/// provides more elements than the ring can hold before you consume them, the DMA
+/// channel overwrites unread elements. The next [`try_read`](ReadRing::try_read)
+/// or [`read`](ReadRing::read) returns an error that satisfies
+/// [`is_overrun`](Error::is_overrun), and discards the ring's elements. The ring
+/// then continues with the elements that the DMA channel writes next.
+///
+/// The ring always detects an overrun of less than one buffer length. To detect
+/// longer overruns, the ring counts the channel's half and completion interrupts,
+/// so call [`on_interrupt()`](crate::Dma::on_interrupt) in your DMA interrupt handler.
+/// [`available`](ReadRing::available) and [`peek`](ReadRing::peek) don't check
+/// for overruns.
+///
+/// Use [`read_ring()`](crate::peripheral::read_ring) to create a ring. Drop the
+/// ring to stop the transfer.
+pub struct ReadRing<'a, S, E>
+where
+    S: Source<E>,
+    E: Element,
+{
+    channel: &'a mut Channel,
+    source: &'a mut S,
+    buffer: *const E,
+    len: usize,
+    /// The index of the next element to consume
+    read: usize,
+    /// The write index when the ring last checked for an overrun
+    write: usize,
+    /// The interrupt count when the ring last checked for an overrun
+    interrupts: u32,
+    /// The channel's CSR configuration before the ring, restored on drop.
+    csr: u16,
+    /// Dropped after the ring stops the channel.
+    _cache: cache::Invalidate,
+    _elem: PhantomData<&'a mut [E]>,
+}
+
+/// Continuously receive data from a peripheral into a circular `buffer`
+///
+/// `buffer` is a [`CircularBuffer`], or an array or slice of any length. See
+/// [`RingBuffer`] for the differences.
+///
+/// `read_ring` starts the transfer before it returns. See [`ReadRing`] for more
+/// information.
+///
+/// A future returned by [`read`](ReadRing::read) resolves when the ring has data.
+/// The ring cannot wake the executor for each element. Instead, `read_ring` enables
+/// the channel's half and completion interrupts, until you drop the ring. Call [`on_interrupt()`](crate::Dma::on_interrupt)
+/// in your DMA interrupt handler to wake the executor when the ring is half full,
+/// and when it wraps. Otherwise, poll the future.
+///
+/// # Panics
+///
+/// Panics if the buffer's length is less than two, or exceeds 2^14.
+///
+/// # Example
+///
+/// Receive bytes from a LPUART peripheral, and echo them back.
+///
+/// ```no_run
+/// use imxrt_dma::{peripheral, channel::Channel, CircularBuffer};
+/// # static DMA: imxrt_dma::Dma<32> = unsafe { imxrt_dma::Dma::new(core::ptr::null(), core::ptr::null()) };
+/// # struct X;
+/// # unsafe impl peripheral::Source<u8> for X {
+/// #   fn source_signal(&self) -> u32 { 0 }
+/// #   fn source_address(&self) -> *const u8 { panic!() }
+/// #   fn enable_source(&mut self) { panic!() }
+/// #   fn disable_source(&mut self) { panic!() }
+/// # }
+/// # fn echo(_: &[u8]) {}
+/// // #[cortex_m_rt::interrupt]
+/// fn DMA7() {
+///     // Safety: DMA channel 7 valid and used by a ring.
+///     unsafe { DMA.on_interrupt(7) };
+/// }
+///
+/// # async fn f() -> imxrt_dma::Result<()> {
+/// let mut lpuart = // A LPUART peripheral
+///     # X;
+/// let mut channel_7: Channel = // DMA channel 7
+///     # unsafe { DMA.channel(7) };
+/// // TODO unmask interrupts in NVIC!
+///
+/// let mut buffer = CircularBuffer::new([0; 64]);
+/// let mut ring = peripheral::read_ring(&mut channel_7, &mut lpuart, &mut buffer);
+/// let mut data = [0; 16];
+/// loop {
+///     let len = ring.read(&mut data).await?;
+///     echo(&data[..len]);
+/// }
+/// # Ok(()) }
+/// ```
+pub fn read_ring<'a, S, E, B>(
+    channel: &'a mut Channel,
+    source: &'a mut S,
+    buffer: &'a mut B,
+) -> ReadRing<'a, S, E>
+where
+    S: Source<E>,
+    E: Element,
+    B: RingBuffer<E> + ?Sized,
+{
+    let len = buffer.as_mut().len();
+    assert!(
+        (2..=1 << 14).contains(&len),
+        "Invalid DMA read ring buffer length"
+    );
+
+    let cache = cache::receive(buffer.as_mut());
+    channel.disable();
+
+    let csr = {
+        let tcd = channel.tcd();
+        ral::read_reg!(crate::ral::tcd, tcd, CSR) & CONTINUOUS_CSR
+    };
+
+    // Never disable the channel; the buffer's modulo, or the last address
+    // adjustment, returns the channel to the start of the buffer.
+    channel.set_disable_on_completion(false);
+    // Count laps to detect overruns.
+    channel.set_interrupt_on_half(true);
+    channel.set_interrupt_on_completion(true);
+    channel.clear_interrupt();
+    channel.set_channel_configuration(Configuration::enable(source.source_signal()));
+    // Safety: hardware source address must be valid, otherwise impl is unsound.
+    // Destination buffer lifetime captured by the ring. One major loop covers
+    // the destination, so we never exceed the end of the destination.
+    unsafe {
+        channel::set_source_hardware(channel, source.source_address());
+        buffer.set_destination(channel);
+        channel.set_minor_loop_offset(None);
+        channel.set_minor_loop_bytes(core::mem::size_of::<E>() as u32);
+        channel.set_transfer_iterations(len as u16);
+    }
+
+    source.enable_source();
+    let interrupts = channel.shared.interrupts();
+    atomic::fence(atomic::Ordering::SeqCst);
+    // Safety: ring is properly prepared, and the buffer is borrowed for the
+    // lifetime of the ring.
+    unsafe { channel.enable() };
+
+    ReadRing {
+        channel,
+        source,
+        buffer: buffer.as_mut().as_ptr(),
+        len,
+        read: 0,
+        write: 0,
+        interrupts,
+        csr,
+        _cache: cache,
+        _elem: PhantomData,
+    }
+}
+
+impl<'a, S, E> ReadRing<'a, S, E>
+where
+    S: Source<E>,
+    E: Element,
+{
+    /// Returns the index of the slot that the DMA channel writes next
+    fn write_index(&self) -> usize {
+        // The channel reloads the iterations when it completes the major loop,
+        // which covers the whole buffer.
+        (self.channel.beginning_transfer_iterations() - self.channel.current_transfer_iterations())
+            as usize
+    }
+
+    /// Returns the number of elements that you can consume
+    pub fn available(&self) -> usize {
+        (self.write_index() + self.len - self.read) % self.len
+    }
+
+    /// Returns the next element without consuming it
+    ///
+    /// Returns `None` if the ring is empty.
+    pub fn peek(&self) -> Option<E> {
+        if self.available() == 0 {
+            return None;
+        }
+        atomic::fence(atomic::Ordering::SeqCst);
+        // Safety: the index is within the buffer, and the DMA channel is not
+        // writing this slot.
+        unsafe {
+            let slot = self.buffer.add(self.read);
+            cache::invalidate(slot, 1);
+            Some(slot.read_volatile())
+        }
+    }
+
+    /// Checks if the DMA channel overwrote unread elements since the last check
+    ///
+    /// On overrun, discards the ring's elements.
+    fn check_overrun(&mut self) -> Result<(), Error> {
+        // Read the count before the write index. The count trails the channel
+        // while an interrupt is pending, which only delays detection.
+        let interrupts = self.channel.shared.interrupts();
+        let write = self.write_index();
+        let written = (write + self.len - self.write) % self.len;
+
+        // The channel interrupts when it writes the first half, and when it
+        // wraps. Every other interrupt is from a whole lap of the buffer.
+        let half = self.len - self.len / 2;
+        let passed = [half, self.len]
+            .into_iter()
+            .filter(|boundary| (boundary + self.len - self.write - 1) % self.len < written)
+            .count() as i32;
+        let laps = (interrupts.wrapping_sub(self.interrupts) as i32 - passed).max(0) as usize / 2;
+
+        let unread = (self.write + self.len - self.read) % self.len;
+        self.write = write;
+        self.interrupts = interrupts;
+        if unread + written + laps * self.len >= self.len {
+            self.read = write;
+            Err(Error::OVERRUN)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Consume elements into `buffer`, without waiting
+    ///
+    /// Returns the number of elements copied into `buffer`. Returns zero if the
+    /// ring is empty. If the DMA channel overwrote unread elements, returns an
+    /// [overrun](Error::is_overrun) error, and discards the ring's elements.
+    pub fn try_read(&mut self, buffer: &mut [E]) -> Result<usize, Error> {
+        self.check_overrun()?;
+        let unread = (self.write + self.len - self.read) % self.len;
+        let count = unread.min(buffer.len());
+        atomic::fence(atomic::Ordering::SeqCst);
+        runs(self.read, count, self.len, |start, len| {
+            // Safety: the run is within the buffer.
+            cache::invalidate(unsafe { self.buffer.add(start) }, len)
+        });
+        for (idx, elem) in buffer[..count].iter_mut().enumerate() {
+            // Safety: the index is within the buffer, and the DMA channel is
+            // not writing available slots.
+            *elem = unsafe {
+                self.buffer
+                    .add((self.read + idx) % self.len)
+                    .read_volatile()
+            };
+        }
+        atomic::fence(atomic::Ordering::SeqCst);
+        // The DMA channel may have overwritten elements while we copied them.
+        self.check_overrun()?;
+        self.read = (self.read + count) % self.len;
+        Ok(count)
+    }
+
+    /// Wait for data, then consume elements into `buffer`
+    ///
+    /// The future resolves with the number of elements copied into `buffer`, once
+    /// the ring has at least one element. If `buffer` is empty, the future
+    /// resolves immediately. See [`try_read`](ReadRing::try_read) for overruns.
+    pub fn read<'r>(&'r mut self, buffer: &'r mut [E]) -> RingRead<'r, 'a, S, E> {
+        RingRead { ring: self, buffer }
+    }
+}
+
+impl<S, E> Drop for ReadRing<'_, S, E>
+where
+    S: Source<E>,
+    E: Element,
+{
+    fn drop(&mut self) {
+        self.source.disable_source();
+        while self.channel.is_hardware_signaling() {}
+        self.channel.cancel();
+        self.channel.clear_complete();
+        self.channel.clear_error();
+        crate::interrupt::set_waker(self.channel, None);
+
+        // Don't leave the ring's interrupts for the channel's next user.
+        let tcd = self.channel.tcd();
+        let csr = ral::read_reg!(crate::ral::tcd, tcd, CSR) & !CONTINUOUS_CSR;
+        ral::write_reg!(crate::ral::tcd, tcd, CSR, csr | self.csr);
+    }
+}
+
+/// Waits for data from a [`ReadRing`]
+///
+/// Use [`read()`](crate::peripheral::ReadRing::read) to create this future.
+pub struct RingRead<'r, 'a, S, E>
+where
+    S: Source<E>,
+    E: Element,
+{
+    ring: &'r mut ReadRing<'a, S, E>,
+    buffer: &'r mut [E],
+}
+
+impl<S, E> Future for RingRead<'_, '_, S, E>
+where
+    S: Source<E>,
+    E: Element,
+{
+    type Output = Result<usize, Error>;
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        crate::interrupt::set_waker(this.ring.channel, Some(cx.waker()));
+
+        if this.ring.channel.is_error() {
+            let es = this.ring.channel.error_status();
+            this.ring.channel.clear_error();
+            return Poll::Ready(Err(es));
+        }
+
+        match this.ring.try_read(this.buffer) {
+            Ok(0) if !this.buffer.is_empty() => Poll::Pending,
+            result => Poll::Ready(result),
+        }
+    }
+}
+
+/// A circular buffer that continuously sends data to hardware
+///
+/// You enqueue elements into the ring with [`try_write`](WriteRing::try_write), and
+/// the DMA channel sends them from a [`RingBuffer`] to the peripheral. Writing to the ring never blocks. If
+/// the ring is full, `try_write` enqueues as many elements as fit.
+///
+/// When you enqueue elements while the DMA channel is idle, the ring starts the DMA
+/// channel. Elements that you enqueue while the DMA channel is busy wait for the next
+/// write, or for a [`flush`](WriteRing::flush). The DMA interrupt doesn't restart the
+/// channel, so poll a flush until it resolves to be sure that the ring sends all
+/// enqueued elements. The ring holds at most one less element than its buffer.
+///
+/// A ring of bytes implements [`core::fmt::Write`]. Use it as a non-blocking sink for
+/// logging and console output.
+///
+/// Use [`write_ring()`](crate::peripheral::write_ring) to create a ring. Drop the
+/// ring to stop the transfer. Dropping the ring discards any elements that the DMA
+/// channel has not sent.
+pub struct WriteRing<'a, D, E>
+where
+    D: Destination<E>,
+    E: Element,
+{
+    channel: &'a mut Channel,
+    destination: &'a mut D,
+    buffer: *mut E,
+    len: usize,
+    /// `true` if the DMA channel wraps within the buffer
+    modulo: bool,
+    /// The index of the next element that the DMA channel sends
+    read: usize,
+    /// The index of the next slot to fill
+    write: usize,
+    /// The number of elements in the DMA channel's current transfer
+    in_flight: usize,
+    /// The error of a transfer that failed outside of a flush
+    error: Option<Error>,
+    _elem: PhantomData<&'a mut [E]>,
+}
+
+/// Continuously send data from a circular `buffer` to a peripheral
+///
+/// `buffer` is a [`CircularBuffer`], or an array or slice of any length. See
+/// [`RingBuffer`] for the differences.
+///
+/// See [`WriteRing`] for more information.
+///
+/// To wake the executor when the DMA channel finishes sending the enqueued
+/// elements, enable the completion interrupt, and call
+/// [`on_interrupt()`](crate::Dma::on_interrupt) in your DMA interrupt handler.
+/// Each poll of a [`flush`](WriteRing::flush) restarts the channel with the
+/// elements that waited; without a flush, they wait for the next write.
+///
+/// # Panics
+///
+/// Panics if the buffer's length is less than two, or exceeds 2^14.
+///
+/// # Example
+///
+/// Format log messages into a LPUART ring, without blocking.
+///
+/// ```no_run
+/// use imxrt_dma::{peripheral, channel::Channel, CircularBuffer};
+/// use core::fmt::Write;
+/// # static DMA: imxrt_dma::Dma<32> = unsafe { imxrt_dma::Dma::new(core::ptr::null(), core::ptr::null()) };
+/// # struct X;
+/// # unsafe impl peripheral::Destination<u8> for X {
+/// #   fn destination_signal(&self) -> u32 { 0 }
+/// #   fn destination_address(&self) -> *const u8 { panic!() }
+/// #   fn enable_destination(&mut self) { panic!() }
+/// #   fn disable_destination(&mut self) { panic!() }
+/// # }
+/// # async fn f() -> imxrt_dma::Result<()> {
+/// let mut lpuart = // A LPUART peripheral
+///     # X;
+/// let mut channel_8: Channel = // DMA channel 8
+///     # unsafe { DMA.channel(8) };
+///
+/// let mut buffer = CircularBuffer::new([0; 256]);
+/// let mut ring = peripheral::write_ring(&mut channel_8, &mut lpuart, &mut buffer);
+/// for tick in 0..100 {
+///     // Ignore the error if the ring is full.
+///     writeln!(ring, "Tick {tick}").ok();
+/// }
+/// ring.flush().await?;
+/// # Ok(()) }
+/// ```
+pub fn write_ring<'a, D, E, B>(
+    channel: &'a mut Channel,
+    destination: &'a mut D,
+    buffer: &'a mut B,
+) -> WriteRing<'a, D, E>
+where
+    D: Destination<E>,
+    E: Element,
+    B: RingBuffer<E> + ?Sized,
+{
+    let len = buffer.as_mut().len();
+    assert!(
+        (2..=1 << 14).contains(&len),
+        "Invalid DMA write ring buffer length"
+    );
+
+    channel.disable();
+
+    channel.set_disable_on_completion(true);
+    channel.set_channel_configuration(Configuration::enable(destination.destination_signal()));
+    // Safety: hardware destination address must be valid, otherwise impl is unsound.
+    // Source buffer lifetime captured by the ring. Either the circular buffer
+    // modulo, or the ring itself, ensures that we never exceed the end of the
+    // source.
+    unsafe {
+        buffer.set_source(channel);
+        channel::set_destination_hardware(channel, destination.destination_address());
+        channel.set_minor_loop_offset(None);
+        channel.set_minor_loop_bytes(core::mem::size_of::<E>() as u32);
+    }
+
+    destination.enable_destination();
+
+    WriteRing {
+        channel,
+        destination,
+        buffer: buffer.as_mut().as_mut_ptr(),
+        len,
+        modulo: B::MODULO,
+        read: 0,
+        write: 0,
+        in_flight: 0,
+        error: None,
+        _elem: PhantomData,
+    }
+}
+
+impl<'a, D, E> WriteRing<'a, D, E>
+where
+    D: Destination<E>,
+    E: Element,
+{
+    /// Returns the number of elements that you can enqueue
+    pub fn vacant(&self) -> usize {
+        self.len - 1 - (self.write + self.len - self.read) % self.len
+    }
+
+    /// Enqueue elements from `data`, without waiting
+    ///
+    /// Returns the number of elements enqueued. If the ring is full, this is
+    /// less than the length of `data`. If a transfer failed, the next
+    /// [`flush`](WriteRing::flush) returns its error.
+    pub fn try_write(&mut self, data: &[E]) -> usize {
+        // Retire a finished transfer, so that we can reuse its slots.
+        if let Err(error) = self.retire() {
+            self.error.get_or_insert(error);
+        }
+
+        let count = self.vacant().min(data.len());
+        for (idx, elem) in data[..count].iter().enumerate() {
+            // Safety: the index is within the buffer, and the DMA channel is not
+            // reading vacant slots.
+            unsafe {
+                self.buffer
+                    .add((self.write + idx) % self.len)
+                    .write_volatile(*elem)
+            };
+        }
+        runs(self.write, count, self.len, |start, len| {
+            // Safety: the run is within the buffer, and we just wrote it.
+            cache::clean(unsafe { core::slice::from_raw_parts(self.buffer.add(start), len) })
+        });
+        self.write = (self.write + count) % self.len;
+        self.start();
+        count
+    }
+
+    /// Wait for the DMA channel to send all enqueued elements
+    ///
+    /// The ring only restarts the DMA channel when you write, or when you poll
+    /// this future. Poll the future until it resolves to send elements that
+    /// you enqueued while the channel was busy.
+    ///
+    /// The future resolves with the error of the first transfer that failed
+    /// since the last flush. The ring discards the elements of a failed transfer.
+    pub fn flush(&mut self) -> RingFlush<'_, 'a, D, E> {
+        RingFlush { ring: self }
+    }
+
+    /// Account for a finished transfer
+    ///
+    /// Returns an error if the transfer failed. The ring discards the elements
+    /// of a failed transfer.
+    fn retire(&mut self) -> Result<(), Error> {
+        if self.in_flight == 0 {
+            return Ok(());
+        }
+
+        let result = if self.channel.is_error() {
+            let es = self.channel.error_status();
+            self.channel.cancel();
+            self.channel.clear_error();
+            Err(es)
+        } else if !self.channel.is_enabled() {
+            // The channel disables itself when it completes.
+            Ok(())
+        } else {
+            return Ok(());
+        };
+
+        self.channel.clear_complete();
+        self.read = (self.read + self.in_flight) % self.len;
+        self.in_flight = 0;
+        result
+    }
+
+    /// Start sending the enqueued elements, if the DMA channel is idle
+    fn start(&mut self) {
+        let mut pending = (self.write + self.len - self.read) % self.len;
+        if !self.modulo {
+            // Without a modulo, the channel cannot wrap within this transfer.
+            pending = pending.min(self.len - self.read);
+        }
+        if self.in_flight != 0 || pending == 0 {
+            return;
+        }
+
+        // Safety: the DMA channel is idle. The address is within the source
+        // buffer. Either the modulo keeps the channel within the source buffer,
+        // or the transfer ends before the end of the source buffer. There are
+        // `pending` elements ready to send.
+        unsafe {
+            self.channel.set_source_address(self.buffer.add(self.read));
+            self.channel.set_transfer_iterations(pending as u16);
+        }
+        self.in_flight = pending;
+        atomic::fence(atomic::Ordering::SeqCst);
+        // Safety: ring is properly prepared, and the buffer is borrowed for the
+        // lifetime of the ring.
+        unsafe { self.channel.enable() };
+    }
+}
+
+impl<D> fmt::Write for WriteRing<'_, D, u8>
+where
+    D: Destination<u8>,
+{
+    /// Enqueue the string's bytes
+    ///
+    /// Returns an error if the ring cannot hold all of the bytes. In that case,
+    /// the ring holds the bytes that fit. Also returns an error if a transfer
+    /// failed since the last [`flush`](WriteRing::flush); the flush returns the
+    /// transfer's error.
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.try_write(s.as_bytes()) == s.len() && self.error.is_none() {
+            Ok(())
+        } else {
+            Err(fmt::Error)
+        }
+    }
+}
+
+impl<D, E> Drop for WriteRing<'_, D, E>
+where
+    D: Destination<E>,
+    E: Element,
+{
+    fn drop(&mut self) {
+        self.destination.disable_destination();
+        while self.channel.is_hardware_signaling() {}
+        self.channel.cancel();
+        self.channel.clear_complete();
+        self.channel.clear_error();
+        crate::interrupt::set_waker(self.channel, None);
+    }
+}
+
+/// Waits for a [`WriteRing`] to send all enqueued elements
+///
+/// Use [`flush()`](crate::peripheral::WriteRing::flush) to create this future.
+pub struct RingFlush<'r, 'a, D, E>
+where
+    D: Destination<E>,
+    E: Element,
+{
+    ring: &'r mut WriteRing<'a, D, E>,
+}
+
+impl<D, E> Future for RingFlush<'_, '_, D, E>
+where
+    D: Destination<E>,
+    E: Element,
+{
+    type Output = Result<(), Error>;
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let ring = &mut *self.ring;
+        crate::interrupt::set_waker(ring.channel, Some(cx.waker()));
+
+        if let Some(error) = ring.error.take() {
+            return Poll::Ready(Err(error));
+        }
+        ring.retire()?;
+        ring.start();
+        if ring.in_flight == 0 {
+            Poll::Ready(Ok(()))
+        } else {
+            Poll::Pending
+        }
+    }
+}
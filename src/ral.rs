@@ -15,7 +15,10 @@ pub mod dma;
 pub mod dmamux;
 pub mod tcd;
 
+#[cfg(feature = "sim")]
+use crate::sim::{RORegister, RWRegister, WORegister};
 pub use ral_registers::{modify_reg, read_reg, write_reg};
+#[cfg(not(feature = "sim"))]
 use ral_registers::{RORegister, RWRegister, WORegister};
 
 //
@@ -34,7 +37,7 @@ impl<T> core::ops::Deref for Static<T> {
 }
 impl<T> Clone for Static<T> {
     fn clone(&self) -> Self {
-        Static(self.0)
+        *self
     }
 }
 impl<T> Copy for Static<T> {}
@@ -1,5 +1,7 @@
 //! DMA register blocks and fields
 
+#![allow(non_upper_case_globals)]
+
 use super::{tcd, RORegister, RWRegister, WORegister};
 
 use core::ops::Index;
@@ -53,6 +55,169 @@ pub struct RegisterBlock {
     pub TCD: [tcd::RegisterBlock; 32],
 }
 
+pub mod CR {
+    /// Enable Debug
+    pub mod EDBG {
+        /// Offset (1 bits)
+        pub const offset: u32 = 1;
+        /// Mask (1 bit: 1 << 1)
+        pub const mask: u32 = 1 << offset;
+        /// Read-only values (empty)
+        pub mod R {}
+        /// Write-only values (empty)
+        pub mod W {}
+        /// Read-write values (empty)
+        pub mod RW {}
+    }
+
+    /// Enable Round Robin Channel Arbitration
+    pub mod ERCA {
+        /// Offset (2 bits)
+        pub const offset: u32 = 2;
+        /// Mask (1 bit: 1 << 2)
+        pub const mask: u32 = 1 << offset;
+        /// Read-only values (empty)
+        pub mod R {}
+        /// Write-only values (empty)
+        pub mod W {}
+        /// Read-write values (empty)
+        pub mod RW {}
+    }
+
+    /// Enable Round Robin Group Arbitration
+    pub mod ERGA {
+        /// Offset (3 bits)
+        pub const offset: u32 = 3;
+        /// Mask (1 bit: 1 << 3)
+        pub const mask: u32 = 1 << offset;
+        /// Read-only values (empty)
+        pub mod R {}
+        /// Write-only values (empty)
+        pub mod W {}
+        /// Read-write values (empty)
+        pub mod RW {}
+    }
+
+    /// Halt On Error
+    pub mod HOE {
+        /// Offset (4 bits)
+        pub const offset: u32 = 4;
+        /// Mask (1 bit: 1 << 4)
+        pub const mask: u32 = 1 << offset;
+        /// Read-only values (empty)
+        pub mod R {}
+        /// Write-only values (empty)
+        pub mod W {}
+        /// Read-write values (empty)
+        pub mod RW {}
+    }
+
+    /// Halt DMA Operations
+    pub mod HALT {
+        /// Offset (5 bits)
+        pub const offset: u32 = 5;
+        /// Mask (1 bit: 1 << 5)
+        pub const mask: u32 = 1 << offset;
+        /// Read-only values (empty)
+        pub mod R {}
+        /// Write-only values (empty)
+        pub mod W {}
+        /// Read-write values (empty)
+        pub mod RW {}
+    }
+
+    /// Continuous Link Mode
+    pub mod CLM {
+        /// Offset (6 bits)
+        pub const offset: u32 = 6;
+        /// Mask (1 bit: 1 << 6)
+        pub const mask: u32 = 1 << offset;
+        /// Read-only values (empty)
+        pub mod R {}
+        /// Write-only values (empty)
+        pub mod W {}
+        /// Read-write values (empty)
+        pub mod RW {}
+    }
+
+    /// Cancel Transfer
+    pub mod CX {
+        /// Offset (17 bits)
+        pub const offset: u32 = 17;
+        /// Mask (1 bit: 1 << 17)
+        pub const mask: u32 = 1 << offset;
+        /// Read-only values (empty)
+        pub mod R {}
+        /// Write-only values (empty)
+        pub mod W {}
+        /// Read-write values (empty)
+        pub mod RW {}
+    }
+
+    /// Error Cancel Transfer
+    pub mod ECX {
+        /// Offset (16 bits)
+        pub const offset: u32 = 16;
+        /// Mask (1 bit: 1 << 16)
+        pub const mask: u32 = 1 << offset;
+        /// Read-only values (empty)
+        pub mod R {}
+        /// Write-only values (empty)
+        pub mod W {}
+        /// Read-write values (empty)
+        pub mod RW {}
+    }
+
+    /// Enable Minor Loop Mapping
+    pub mod EMLM {
+        /// Offset (7 bits)
+        pub const offset: u32 = 7;
+        /// Mask (1 bit: 1 << 7)
+        pub const mask: u32 = 1 << offset;
+        /// Read-only values (empty)
+        pub mod R {}
+        /// Write-only values (empty)
+        pub mod W {}
+        /// Read-write values (empty)
+        pub mod RW {}
+    }
+}
+
+/// Fields of a channel priority register
+///
+/// Since the channel priority registers are accessed by index,
+/// these fields are used directly, not through the RAL macros.
+pub mod DCHPRI {
+    /// Channel Arbitration Priority
+    pub mod CHPRI {
+        /// Offset (0 bits)
+        pub const offset: u8 = 0;
+        /// Mask (4 bits: 0b1111 << 0)
+        pub const mask: u8 = 0b1111 << offset;
+    }
+    /// Channel Current Group Priority
+    pub mod GRPPRI {
+        /// Offset (4 bits)
+        pub const offset: u8 = 4;
+        /// Mask (2 bits: 0b11 << 4)
+        pub const mask: u8 = 0b11 << offset;
+    }
+    /// Disable Preempt Ability
+    pub mod DPA {
+        /// Offset (6 bits)
+        pub const offset: u8 = 6;
+        /// Mask (1 bit: 1 << 6)
+        pub const mask: u8 = 1 << offset;
+    }
+    /// Enable Channel Preemption
+    pub mod ECP {
+        /// Offset (7 bits)
+        pub const offset: u8 = 7;
+        /// Mask (1 bit: 1 << 7)
+        pub const mask: u8 = 1 << offset;
+    }
+}
+
 /// Wrapper for channel priority registers
 ///
 /// Channel priority registers cannot be accessed with
@@ -27,6 +27,16 @@ const _STATIC_ASSERT_TCD_32_BYTES: [u32; 1] =
     [0; (32 == core::mem::size_of::<RegisterBlock>()) as usize];
 
 impl RegisterBlock {
+    /// Returns a TCD with all fields set to zero
+    ///
+    /// Use this for TCDs that live in memory, not for the TCDs
+    /// in the DMA controller.
+    pub const fn zeroed() -> Self {
+        // Safety: all fields are (cells of) integers, and zero is
+        // a valid integer.
+        unsafe { core::mem::zeroed() }
+    }
+
     /// TCDs are uninitialized after reset. Set them to a known,
     /// good state here.
     pub fn reset(&self) {
@@ -85,6 +95,20 @@ pub mod SATTR {
 
 pub mod CSR {
 
+    /// Channel Start
+    pub mod START {
+        /// Offset (0 bits)
+        pub const offset: u16 = 0;
+        /// Mask (1 bit: 1 << 0)
+        pub const mask: u16 = 1 << offset;
+        /// Read-only values (empty)
+        pub mod R {}
+        /// Write-only values (empty)
+        pub mod W {}
+        /// Read-write values (empty)
+        pub mod RW {}
+    }
+
     /// Enable an interrupt when major iteration count completes.
     pub mod INTMAJOR {
         /// Offset (1 bits)
@@ -99,6 +123,20 @@ pub mod CSR {
         pub mod RW {}
     }
 
+    /// Enable an interrupt when major counter is half complete.
+    pub mod INTHALF {
+        /// Offset (2 bits)
+        pub const offset: u16 = 2;
+        /// Mask (1 bit: 1 << 2)
+        pub const mask: u16 = 1 << offset;
+        /// Read-only values (empty)
+        pub mod R {}
+        /// Write-only values (empty)
+        pub mod W {}
+        /// Read-write values
+        pub mod RW {}
+    }
+
     /// Disable Request
     pub mod DREQ {
         /// Offset (3 bits)
@@ -113,6 +151,48 @@ pub mod CSR {
         pub mod RW {}
     }
 
+    /// Enable Scatter/Gather Processing
+    pub mod ESG {
+        /// Offset (4 bits)
+        pub const offset: u16 = 4;
+        /// Mask (1 bit: 1 << 4)
+        pub const mask: u16 = 1 << offset;
+        /// Read-only values (empty)
+        pub mod R {}
+        /// Write-only values (empty)
+        pub mod W {}
+        /// Read-write values
+        pub mod RW {}
+    }
+
+    /// Enable channel-to-channel linking on major loop complete
+    pub mod MAJORELINK {
+        /// Offset (5 bits)
+        pub const offset: u16 = 5;
+        /// Mask (1 bit: 1 << 5)
+        pub const mask: u16 = 1 << offset;
+        /// Read-only values (empty)
+        pub mod R {}
+        /// Write-only values (empty)
+        pub mod W {}
+        /// Read-write values (empty)
+        pub mod RW {}
+    }
+
+    /// Major Loop Link Channel Number
+    pub mod MAJORLINKCH {
+        /// Offset (8 bits)
+        pub const offset: u16 = 8;
+        /// Mask (5 bits: 0b11111 << 8)
+        pub const mask: u16 = 0b11111 << offset;
+        /// Read-only values (empty)
+        pub mod R {}
+        /// Write-only values (empty)
+        pub mod W {}
+        /// Read-write values (empty)
+        pub mod RW {}
+    }
+
     /// Channel Done
     pub mod DONE {
         /// Offset (7 bits)
@@ -166,6 +246,41 @@ pub mod CSR {
     }
 }
 
+pub mod NBYTES {
+    /// Minor Byte Transfer Count, when minor loop offsets are enabled
+    pub mod NBYTES_MLOFFYES {
+        pub const offset: u32 = 0;
+        pub const mask: u32 = 0x3ff << offset;
+        pub mod R {}
+        pub mod W {}
+        pub mod RW {}
+    }
+    /// Minor Loop Offset
+    pub mod MLOFF {
+        pub const offset: u32 = 10;
+        pub const mask: u32 = 0xfffff << offset;
+        pub mod R {}
+        pub mod W {}
+        pub mod RW {}
+    }
+    /// Destination Minor Loop Offset enable
+    pub mod DMLOE {
+        pub const offset: u32 = 30;
+        pub const mask: u32 = 1 << offset;
+        pub mod R {}
+        pub mod W {}
+        pub mod RW {}
+    }
+    /// Source Minor Loop Offset Enable
+    pub mod SMLOE {
+        pub const offset: u32 = 31;
+        pub const mask: u32 = 1 << offset;
+        pub mod R {}
+        pub mod W {}
+        pub mod RW {}
+    }
+}
+
 pub mod CITER {
     /// Current Major Iteration Count
     pub mod CITER {
@@ -175,6 +290,30 @@ pub mod CITER {
         pub mod W {}
         pub mod RW {}
     }
+    /// Current Major Iteration Count, when ELINK is set
+    pub mod CITER_ELINKYES {
+        pub const offset: u16 = 0;
+        pub const mask: u16 = 0x1ff << offset;
+        pub mod R {}
+        pub mod W {}
+        pub mod RW {}
+    }
+    /// Minor Loop Link Channel Number
+    pub mod LINKCH {
+        pub const offset: u16 = 9;
+        pub const mask: u16 = 0x1f << offset;
+        pub mod R {}
+        pub mod W {}
+        pub mod RW {}
+    }
+    /// Enable channel-to-channel linking on minor-loop complete
+    pub mod ELINK {
+        pub const offset: u16 = 15;
+        pub const mask: u16 = 1 << offset;
+        pub mod R {}
+        pub mod W {}
+        pub mod RW {}
+    }
 }
 
 pub mod BITER {
@@ -186,6 +325,30 @@ pub mod BITER {
         pub mod W {}
         pub mod RW {}
     }
+    /// Starting Major Iteration Count, when ELINK is set
+    pub mod BITER_ELINKYES {
+        pub const offset: u16 = 0;
+        pub const mask: u16 = 0x1ff << offset;
+        pub mod R {}
+        pub mod W {}
+        pub mod RW {}
+    }
+    /// Minor Loop Link Channel Number
+    pub mod LINKCH {
+        pub const offset: u16 = 9;
+        pub const mask: u16 = 0x1f << offset;
+        pub mod R {}
+        pub mod W {}
+        pub mod RW {}
+    }
+    /// Enable channel-to-channel linking on minor-loop complete
+    pub mod ELINK {
+        pub const offset: u16 = 15;
+        pub const mask: u16 = 1 << offset;
+        pub mod R {}
+        pub mod W {}
+        pub mod RW {}
+    }
 }
 
 /// Throttles the amount of bus bandwidth consumed by the eDMA
@@ -0,0 +1,403 @@
+//! Scatter-gather DMA transfers
+//!
+//! A scatter-gather transfer is a sequence of transfer control descriptors
+//! ([`Tcd`]s) that live in memory. When the DMA channel completes the major
+//! loop of one descriptor, the DMA engine loads the next descriptor into the
+//! channel, and continues with that transfer. The CPU isn't involved
+//! between descriptors.
+//!
+//! Define each descriptor with the `Tcd` methods. Then, use [`chain`] to run
+//! the descriptors once, or [`cycle`] to run them in a loop. Both functions
+//! link the descriptors for you.
+//!
+//! `Tcd` methods that specify memory involved in transfers are marked `unsafe`.
+//! The same rules apply as for [`Channel`]: you must make sure that the memory
+//! is valid for the lifetime of the transfer.
+
+use crate::{
+    channel::Channel,
+    element::Element,
+    interrupt::Transfer,
+    ral::{self, tcd::BandwidthControl},
+    Error,
+};
+
+use core::{
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// A transfer control descriptor that lives in memory
+///
+/// A `Tcd` has the same layout as the DMA channel's transfer control descriptor.
+/// It's aligned to 32 bytes, as required by the DMA engine. A new `Tcd` has all
+/// fields set to zero.
+///
+/// The methods reflect the [`Channel`] methods that define a transfer. Unlike
+/// `Channel`, these methods only change memory; nothing happens until you
+/// use the descriptor in a [`chain`] or [`cycle`].
+#[repr(transparent)]
+pub struct Tcd(pub(crate) ral::tcd::RegisterBlock);
+
+impl Default for Tcd {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tcd {
+    /// Create a new transfer control descriptor
+    ///
+    /// All fields are zero.
+    pub const fn new() -> Self {
+        Tcd(ral::tcd::RegisterBlock::zeroed())
+    }
+
+    /// Set the source address for a DMA transfer
+    ///
+    /// # Safety
+    ///
+    /// You must ensure that reads to `saddr` do not perform inappropriate
+    /// side effects. You must ensure `saddr` is valid for the lifetime of
+    /// the transfer.
+    pub unsafe fn set_source_address<E: Element>(&mut self, saddr: *const E) {
+        let tcd = &self.0;
+        ral::write_reg!(crate::ral::tcd, tcd, SADDR, saddr as u32);
+    }
+
+    /// Set the source offset *in bytes*
+    ///
+    /// # Safety
+    ///
+    /// This method could allow a DMA engine to read beyond a buffer or
+    /// address. You must ensure that the source is valid for these offsets.
+    pub unsafe fn set_source_offset(&mut self, offset: i16) {
+        let tcd = &self.0;
+        ral::write_reg!(crate::ral::tcd, tcd, SOFF, offset);
+    }
+
+    /// Set the transfer attributes for the source
+    ///
+    /// # Safety
+    ///
+    /// An incorrect `modulo` value may allow the DMA engine to loop back
+    /// to an incorrect address. You must ensure that `modulo` is valid
+    /// for your source.
+    pub unsafe fn set_source_attributes<E: Element>(&mut self, modulo: u8) {
+        let tcd = &self.0;
+        ral::write_reg!(
+            crate::ral::tcd,
+            tcd,
+            SATTR,
+            MOD: modulo,
+            SIZE: E::DATA_TRANSFER_ID
+        );
+    }
+
+    /// Set the source last address adjustment *in bytes*
+    ///
+    /// # Safety
+    ///
+    /// This could allow the DMA engine to reference an invalid source buffer.
+    /// You must ensure that the adjustment is valid for any transfer that
+    /// follows this descriptor.
+    pub unsafe fn set_source_last_address_adjustment(&mut self, adjustment: i32) {
+        let tcd = &self.0;
+        ral::write_reg!(crate::ral::tcd, tcd, SLAST, adjustment);
+    }
+
+    /// Set the destination address for a DMA transfer
+    ///
+    /// # Safety
+    ///
+    /// You must ensure that writes to `daddr` are safe, and that the memory
+    /// is valid for the lifetime of the transfer.
+    pub unsafe fn set_destination_address<E: Element>(&mut self, daddr: *const E) {
+        let tcd = &self.0;
+        ral::write_reg!(crate::ral::tcd, tcd, DADDR, daddr as u32);
+    }
+
+    /// Set the destination offset *in bytes*
+    ///
+    /// # Safety
+    ///
+    /// This method could allow a DMA engine to write beyond the range of
+    /// a buffer. You must ensure that the destination is valid for these
+    /// offsets.
+    pub unsafe fn set_destination_offset(&mut self, offset: i16) {
+        let tcd = &self.0;
+        ral::write_reg!(crate::ral::tcd, tcd, DOFF, offset);
+    }
+
+    /// Set the transfer attributes for the destination
+    ///
+    /// # Safety
+    ///
+    /// An incorrect `modulo` value may allow the DMA engine to loop back
+    /// to an incorrect address. You must ensure that `modulo` is valid
+    /// for your destination.
+    pub unsafe fn set_destination_attributes<E: Element>(&mut self, modulo: u8) {
+        let tcd = &self.0;
+        ral::write_reg!(
+            crate::ral::tcd,
+            tcd,
+            DATTR,
+            MOD: modulo,
+            SIZE: E::DATA_TRANSFER_ID
+        );
+    }
+
+    /// Set the destination last address adjustment *in bytes*
+    ///
+    /// The adjustment only applies to the last descriptor in a [`chain`].
+    /// Otherwise, the DMA engine uses this field to find the next descriptor.
+    ///
+    /// # Safety
+    ///
+    /// This could allow the DMA engine to reference an invalid destination address.
+    /// You must ensure that the adjustment is valid for any transfer that follows
+    /// this descriptor.
+    pub unsafe fn set_destination_last_address_adjustment(&mut self, adjustment: i32) {
+        let tcd = &self.0;
+        ral::modify_reg!(crate::ral::tcd, tcd, CSR, ESG: 0);
+        ral::write_reg!(crate::ral::tcd, tcd, DLAST_SGA, adjustment);
+    }
+
+    /// Set the number of *bytes* to transfer per minor loop
+    ///
+    /// Note that `nbytes` of `0` is interpreted as a 4GB transfer.
+    ///
+    /// # Safety
+    ///
+    /// This might allow the DMA engine to read beyond the source, or write beyond
+    /// the destination. Caller must ensure that the number of bytes per minor loop
+    /// is valid for the given transfer.
+    pub unsafe fn set_minor_loop_bytes(&mut self, nbytes: u32) {
+        let tcd = &self.0;
+        ral::write_reg!(crate::ral::tcd, tcd, NBYTES, nbytes);
+    }
+
+    /// Set the number of transfer iterations, or major loops, for this descriptor
+    ///
+    /// # Safety
+    ///
+    /// This may allow the DMA engine to read beyond the source, or write beyond
+    /// the destination. Caller must ensure that the number of iterations is valid
+    /// for the transfer.
+    pub unsafe fn set_transfer_iterations(&mut self, iterations: u16) {
+        let tcd = &self.0;
+        ral::modify_reg!(crate::ral::tcd, tcd, CITER, CITER: iterations);
+        ral::modify_reg!(crate::ral::tcd, tcd, BITER, BITER: iterations);
+    }
+
+    /// Set the bandwidth control for this descriptor
+    ///
+    /// See [`Channel::set_bandwidth_control`] for more information.
+    pub fn set_bandwidth_control(&mut self, bandwidth: Option<BandwidthControl>) {
+        let raw = BandwidthControl::raw(bandwidth);
+        let tcd = &self.0;
+        ral::modify_reg!(crate::ral::tcd, tcd, CSR, BWC: raw);
+    }
+
+    /// Enable or disable interrupt generation when this descriptor completes
+    pub fn set_interrupt_on_completion(&mut self, intr: bool) {
+        let tcd = &self.0;
+        ral::modify_reg!(crate::ral::tcd, tcd, CSR, INTMAJOR: intr as u16);
+    }
+
+    /// Enable or disable the software start for this descriptor
+    ///
+    /// Set this for memory-to-memory transfers, which have no hardware
+    /// to request DMA service. When the DMA engine loads a descriptor
+    /// with this flag, it immediately starts the descriptor's transfer.
+    pub fn set_start(&mut self, start: bool) {
+        let tcd = &self.0;
+        ral::modify_reg!(crate::ral::tcd, tcd, CSR, START: start as u16);
+    }
+
+    /// Link this descriptor to the `next` descriptor, or make it the last
+    /// descriptor if `next` is `None`
+    pub(crate) fn set_next(&mut self, next: Option<*const Tcd>) {
+        let tcd = &self.0;
+        match next {
+            Some(next) => {
+                ral::write_reg!(crate::ral::tcd, tcd, DLAST_SGA, next as i32);
+                ral::modify_reg!(crate::ral::tcd, tcd, CSR, ESG: 1, DREQ: 0);
+            }
+            None => {
+                if ral::read_reg!(crate::ral::tcd, tcd, CSR, ESG == 1) {
+                    ral::write_reg!(crate::ral::tcd, tcd, DLAST_SGA, 0);
+                }
+                ral::modify_reg!(crate::ral::tcd, tcd, CSR, ESG: 0, DREQ: 1);
+            }
+        }
+    }
+}
+
+/// A scatter-gather transfer
+///
+/// `ScatterGather` runs a sequence of [`Tcd`]s on a DMA channel. Use
+/// [`chain`] or [`cycle`] to create this future.
+pub struct ScatterGather<'a> {
+    transfer: Transfer<'a>,
+    channel: &'a Channel,
+    start: bool,
+    /// Set if the descriptors run in a loop
+    cycle: bool,
+    _tcds: PhantomData<&'a mut [Tcd]>,
+}
+
+/// Link each descriptor to the descriptor that follows it
+///
+/// Doesn't touch the last descriptor.
+fn link(tcds: &mut [Tcd]) {
+    for idx in 1..tcds.len() {
+        let next: *const Tcd = &tcds[idx];
+        tcds[idx - 1].set_next(Some(next));
+    }
+}
+
+fn prepare(channel: &mut Channel, tcds: &[Tcd]) -> bool {
+    channel.disable();
+
+    let first = &tcds[0].0;
+    let start = ral::read_reg!(crate::ral::tcd, first, CSR, START == 1);
+
+    // Safety: descriptors are borrowed for the lifetime of the transfer, and
+    // the caller defined their memory when they set each descriptor.
+    unsafe { channel.load_tcd(&tcds[0]) };
+    start
+}
+
+/// Run a chain of scatter-gather descriptors
+///
+/// `chain` links each descriptor in `tcds` to the next descriptor. Then, it loads
+/// the first descriptor into the DMA channel. The future resolves when the DMA
+/// channel completes the last descriptor.
+///
+/// `chain` overwrites the destination last address adjustment of all but the last
+/// descriptor. It also controls 'disable on completion' for all descriptors.
+///
+/// You're responsible for configuring the channel's DMAMUX source before awaiting
+/// the transfer. If the first descriptor requests a software [`start`](Tcd::set_start),
+/// the future starts the transfer when it's first polled.
+///
+/// To wake the executor when the chain completes, enable the interrupt on completion
+/// for the last descriptor, and call [`on_interrupt`](crate::Dma::on_interrupt) in
+/// your DMA interrupt handler.
+///
+/// # Panics
+///
+/// Panics if `tcds` is empty.
+///
+/// # Example
+///
+/// Copy two buffers into one destination using two descriptors.
+///
+/// ```no_run
+/// use imxrt_dma::{channel::{self, Channel}, scatter_gather::{self, Tcd}};
+///
+/// # static DMA: imxrt_dma::Dma<32> = unsafe { imxrt_dma::Dma::new(core::ptr::null(), core::ptr::null()) };
+/// # async fn f() -> imxrt_dma::Result<()> {
+/// let mut channel_7: Channel = // DMA channel 7
+///     # unsafe { DMA.channel(7) };
+/// channel_7.set_channel_configuration(channel::Configuration::Off);
+///
+/// let first = [1u32; 4];
+/// let second = [2u32; 4];
+/// let mut destination = [0u32; 8];
+///
+/// let mut tcds = [Tcd::new(), Tcd::new()];
+/// for (tcd, (source, offset)) in tcds.iter_mut().zip([(&first, 0), (&second, 4)]) {
+///     // Safety: buffers outlive the transfer.
+///     unsafe {
+///         tcd.set_source_address(source.as_ptr());
+///         tcd.set_source_offset(4);
+///         tcd.set_source_attributes::<u32>(0);
+///         tcd.set_destination_address(destination[offset..].as_ptr());
+///         tcd.set_destination_offset(4);
+///         tcd.set_destination_attributes::<u32>(0);
+///         tcd.set_minor_loop_bytes(16);
+///         tcd.set_transfer_iterations(1);
+///     }
+///     tcd.set_start(true);
+/// }
+/// tcds[1].set_interrupt_on_completion(true);
+///
+/// scatter_gather::chain(&mut channel_7, &mut tcds).await?;
+/// # Ok(()) }
+/// ```
+pub fn chain<'a>(channel: &'a mut Channel, tcds: &'a mut [Tcd]) -> ScatterGather<'a> {
+    assert!(!tcds.is_empty(), "Scatter-gather chain has no descriptors");
+
+    link(tcds);
+    tcds[tcds.len() - 1].set_next(None);
+
+    let start = prepare(channel, tcds);
+    ScatterGather {
+        // Safety: transfer is properly prepared
+        transfer: unsafe { Transfer::new(channel) },
+        channel,
+        start,
+        cycle: false,
+        _tcds: PhantomData,
+    }
+}
+
+/// Run scatter-gather descriptors in a loop
+///
+/// `cycle` links each descriptor in `tcds` to the next descriptor, and links the
+/// last descriptor back to the first descriptor. The DMA channel runs the descriptors
+/// until you drop the future. The future only resolves if there's an error. It
+/// ignores the channel's completion flag, which may be set after each descriptor.
+///
+/// Use the interrupt on completion of each descriptor to learn when the DMA channel
+/// finishes each descriptor. Otherwise, `cycle` has the same requirements as [`chain`].
+///
+/// # Panics
+///
+/// Panics if `tcds` is empty.
+pub fn cycle<'a>(channel: &'a mut Channel, tcds: &'a mut [Tcd]) -> ScatterGather<'a> {
+    assert!(!tcds.is_empty(), "Scatter-gather cycle has no descriptors");
+
+    link(tcds);
+    let first: *const Tcd = &tcds[0];
+    tcds[tcds.len() - 1].set_next(Some(first));
+
+    let start = prepare(channel, tcds);
+    ScatterGather {
+        // Safety: transfer is properly prepared
+        transfer: unsafe { Transfer::new(channel) },
+        channel,
+        start,
+        cycle: true,
+        _tcds: PhantomData,
+    }
+}
+
+impl Future for ScatterGather<'_> {
+    type Output = Result<(), Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let cycle = self.cycle;
+        // Safety: data not moved
+        let mut transfer = unsafe { self.as_mut().map_unchecked_mut(|this| &mut this.transfer) };
+        let poll = loop {
+            match transfer.as_mut().poll(cx) {
+                // A cycle never completes. Keep waiting for an error.
+                Poll::Ready(Ok(())) if cycle => transfer.as_mut().restart(),
+                poll => break poll,
+            }
+        };
+        if poll.is_pending() && self.start {
+            self.channel.start();
+            // Safety: OK to toggle a bool...
+            unsafe { self.as_mut().get_unchecked_mut().start = false };
+        }
+        poll
+    }
+}
+
+// Drop handled by Transfer impl
@@ -0,0 +1,1752 @@
+//! A host-side model of the DMA controller
+//!
+//! The `sim` feature replaces the DMA registers with a software model of the
+//! DMA controller and the DMA multiplexer. Use it to test DMA-driven code on
+//! your development host, without i.MX RT hardware.
+//!
+//! A [`Simulator`] owns RAM-backed DMA registers. Acquire a [`Dma`] driver
+//! from the simulator, then use the driver as you would on hardware. The
+//! simulator executes a channel's transfer control descriptor when you
+//!
+//! - [`step`](Simulator::step) or [`run`](Simulator::run) the simulator, which
+//!   services software-started and always-on channels.
+//! - signal a peripheral [`request`](Simulator::request).
+//!
+//! The model moves data, and it honors offsets, modulo, minor loop offsets,
+//! last address adjustments, scatter-gather, and channel linking. It sets the
+//! DONE, INT and ERR flags. When a channel generates an interrupt, the simulator
+//! calls [`on_interrupt`](Dma::on_interrupt) or [`on_error_interrupt`](Dma::on_error_interrupt).
+//!
+//! The DMA registers hold 32-bit addresses, but host pointers may be wider. The
+//! simulator resolves each address through its memory map. [`map`](Simulator::map)
+//! all memory that a transfer accesses, including buffers, in-memory TCDs, and
+//! simulated peripheral registers. An access to unmapped memory is a bus error.
+//!
+//! Some futures hold their own TCDs. A [`peripheral`](crate::peripheral) transfer
+//! whose buffer exceeds the channel's [maximum transfer
+//! iterations](crate::channel::Channel::max_transfer_iterations) links segments
+//! with TCDs that live in the future. Pin the future, then map it, too.
+//!
+//! The simulator does not model timing. Each minor loop executes to completion, so
+//! a channel is never active when you observe it. Channel priorities determine the
+//! order in which the simulator services channels. The simulator ignores bandwidth
+//! control, round robin arbitration, and preemption.
+//!
+//! # Example
+//!
+//! Test a memcpy without hardware.
+//!
+//! ```
+//! use imxrt_dma::{memcpy, sim::Simulator};
+//! use core::{future::Future, pin::pin, task::{Context, Poll, Waker}};
+//! use std::{sync::Arc, task::Wake};
+//!
+//! /// A waker that does nothing; the test polls the transfer itself.
+//! struct Noop;
+//! impl Wake for Noop {
+//!     fn wake(self: Arc<Self>) {}
+//! }
+//!
+//! let sim = Simulator::new();
+//! let dma = sim.dma::<32>();
+//! // Safety: we only allocate one DMA channel 7 object.
+//! let mut channel = unsafe { dma.channel(7) };
+//!
+//! let source = [4u32, 5, 6, 7, 8];
+//! let mut destination = [0; 5];
+//! // Safety: the buffers outlive the transfer.
+//! unsafe {
+//!     sim.map(&source);
+//!     sim.map(&destination);
+//! }
+//!
+//! let waker = Waker::from(Arc::new(Noop));
+//! let mut cx = Context::from_waker(&waker);
+//! {
+//!     let mut transfer = pin!(memcpy::memcpy(&source, &mut destination, &mut channel));
+//!     assert!(transfer.as_mut().poll(&mut cx).is_pending());
+//!
+//!     sim.run();
+//!     assert!(matches!(transfer.as_mut().poll(&mut cx), Poll::Ready(Ok(()))));
+//! }
+//! assert_eq!(destination, source);
+//! ```
+
+extern crate std;
+
+mod register;
+
+pub(crate) use register::{RORegister, RWRegister, WORegister};
+
+use crate::{
+    ral::{self, dma, dmamux, tcd},
+    Dma,
+};
+use std::{
+    boxed::Box,
+    sync::{Mutex, MutexGuard, OnceLock, PoisonError},
+    vec,
+    vec::Vec,
+};
+
+/// Error status bits
+mod es {
+    pub const DBE: u32 = 1 << 0;
+    pub const SBE: u32 = 1 << 1;
+    pub const SGE: u32 = 1 << 2;
+    pub const NCE: u32 = 1 << 3;
+    pub const DOE: u32 = 1 << 4;
+    pub const DAE: u32 = 1 << 5;
+    pub const SOE: u32 = 1 << 6;
+    pub const SAE: u32 = 1 << 7;
+    pub const ERRCHN_OFFSET: u32 = 8;
+    pub const VLD: u32 = 1 << 31;
+}
+
+/// Command register bits
+mod command {
+    /// Select all channels
+    pub const ALL: u8 = 1 << 6;
+    /// No operation
+    pub const NOP: u8 = 1 << 7;
+    /// Channel number
+    pub const CHANNEL: u8 = 0x1F;
+}
+
+/// Multiplexer source mask
+const SOURCE: u32 = 0x7F;
+
+/// All simulators, for finding the simulator that owns a register
+static SIMULATORS: Mutex<Vec<&'static Simulator>> = Mutex::new(Vec::new());
+
+/// A simulated DMA controller and multiplexer
+///
+/// See the [module-level documentation](crate::sim) for more information.
+pub struct Simulator {
+    dma: dma::RegisterBlock,
+    dmamux: dmamux::RegisterBlock,
+    memory: Mutex<Vec<Region>>,
+    interrupts: OnceLock<Box<dyn Fn(Interrupt) + Send + Sync>>,
+}
+
+// Safety: like the DMA peripheral, the simulated registers are shared memory.
+// The DMA driver coordinates access to the registers, as it does on hardware.
+unsafe impl Sync for Simulator {}
+
+/// Memory that's accessible to the simulated DMA engine
+struct Region {
+    /// Host address
+    host: usize,
+    /// Size, in bytes
+    len: usize,
+}
+
+impl Region {
+    /// Returns the bus address, which is the lower 32 bits of the host address
+    fn bus(&self) -> u32 {
+        self.host as u32
+    }
+
+    /// Indicates if the DMA engine can't tell this region apart from `other`
+    ///
+    /// The regions share bus addresses, but not the same host memory.
+    fn aliases(&self, other: &Region) -> bool {
+        let overlaps = (self.bus() as usize) < (other.bus() as usize).saturating_add(other.len)
+            && (other.bus() as usize) < (self.bus() as usize).saturating_add(self.len);
+        let base = |region: &Region| region.host - region.bus() as usize;
+        overlaps && base(self) != base(other)
+    }
+}
+
+/// An interrupt raised by the simulator
+#[derive(Clone, Copy)]
+enum Interrupt {
+    Channel(usize),
+    Error,
+}
+
+impl Simulator {
+    /// Create a simulator
+    ///
+    /// The simulator lives for the rest of the program. All channels are
+    /// disabled, and each channel's priority is its channel number.
+    pub fn new() -> &'static Self {
+        let simulator: &'static Simulator = Box::leak(Box::new(Simulator {
+            // Safety: the registers are cells of integers, and zero is a valid integer.
+            dma: unsafe { core::mem::zeroed() },
+            dmamux: unsafe { core::mem::zeroed() },
+            memory: Mutex::new(Vec::new()),
+            interrupts: OnceLock::new(),
+        }));
+        for channel in 0..32 {
+            simulator.dma.DCHPRI[channel].set(channel as u8);
+        }
+        lock(&SIMULATORS).push(simulator);
+        simulator
+    }
+
+    /// Create the DMA driver for this simulator
+    ///
+    /// The simulator calls the driver's [`on_interrupt`](Dma::on_interrupt) when a
+    /// channel generates an interrupt, and [`on_error_interrupt`](Dma::on_error_interrupt)
+    /// when a channel that enables the error interrupt has an error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `CHANNELS` exceeds 32, or if you've already created a DMA driver for
+    /// this simulator.
+    pub fn dma<const CHANNELS: usize>(&'static self) -> &'static Dma<CHANNELS> {
+        assert!(CHANNELS <= 32, "The simulator supports at most 32 channels");
+        // Safety: pointers to this simulator's register blocks.
+        let dma: &'static Dma<CHANNELS> = Box::leak(Box::new(unsafe {
+            Dma::new(
+                core::ptr::addr_of!(self.dma).cast(),
+                core::ptr::addr_of!(self.dmamux).cast(),
+            )
+        }));
+        let handler = move |interrupt| match interrupt {
+            // Safety: the simulator raised the interrupt.
+            Interrupt::Channel(channel) if channel < CHANNELS => unsafe {
+                dma.on_interrupt(channel)
+            },
+            Interrupt::Channel(_) => {}
+            // Safety: the simulator raised the interrupt.
+            Interrupt::Error => unsafe { dma.on_error_interrupt() },
+        };
+        assert!(
+            self.interrupts.set(Box::new(handler)).is_ok(),
+            "The simulator already has a DMA driver"
+        );
+        dma
+    }
+
+    /// Map `memory` into the DMA engine's address space
+    ///
+    /// The DMA engine accesses `memory` at the bus address formed by the lower
+    /// 32 bits of its host address. The simulator tracks each mapping by its
+    /// full host address, so you may map the same memory more than once.
+    ///
+    /// # Safety
+    ///
+    /// `memory` must be valid for as long as a transfer may access it. The
+    /// simulator reads and writes `memory` whenever it services a channel that
+    /// accesses it, regardless of any other references to the memory.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `memory` shares bus addresses with other mapped memory, whose host
+    /// address differs in the upper bits. The DMA engine can't tell the two apart.
+    pub unsafe fn map<T: ?Sized>(&self, memory: &T) {
+        let region = Region {
+            host: memory as *const T as *const u8 as usize,
+            len: core::mem::size_of_val(memory),
+        };
+        let mut regions = lock(&self.memory);
+        assert!(
+            !regions.iter().any(|other| other.aliases(&region)),
+            "Mapped memory {:#x} aliases other memory on the simulated bus",
+            region.host
+        );
+        regions.push(region);
+    }
+
+    /// Service one minor loop of the highest priority channel that's requesting service
+    ///
+    /// A channel requests service if software set its START bit, or if its
+    /// multiplexer is always on and it's enabled. Returns `false` if no channel
+    /// requests service, or if the DMA controller is halted.
+    pub fn step(&self) -> bool {
+        let halted = ral::read_reg!(crate::ral::dma, &self.dma, CR, HALT == 1);
+        let erq = self.dma.ERQ.read();
+        let next = (0..32)
+            .filter(|&channel| {
+                let csr = self.dma.TCD[channel].CSR.read();
+                let chcfg = self.dmamux.chcfg[channel].read();
+                let always_on = dmamux::RegisterBlock::ENBL | dmamux::RegisterBlock::A_ON;
+                csr & tcd::CSR::START::mask != 0
+                    || (erq & (1 << channel) != 0 && chcfg & always_on == always_on)
+            })
+            .max_by_key(|&channel| {
+                let priority = self.dma.DCHPRI[channel].read() & dma::DCHPRI::CHPRI::mask;
+                (priority, channel)
+            });
+        match next {
+            Some(channel) if !halted => {
+                self.service(channel);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Step the simulator until no channel requests service
+    ///
+    /// Returns the number of serviced minor loops. `run` does not return if an
+    /// always-on channel requests service forever.
+    pub fn run(&self) -> usize {
+        let mut minor_loops = 0;
+        while self.step() {
+            minor_loops += 1;
+        }
+        minor_loops
+    }
+
+    /// Signal a service request from the peripheral identified by `source`
+    ///
+    /// `source` is the peripheral's DMA multiplexer source. Each enabled channel that's
+    /// routed to `source` executes one minor loop. The request is lost if the DMA
+    /// controller is halted.
+    ///
+    /// # Example
+    ///
+    /// Simulate a UART that receives two bytes.
+    ///
+    /// ```
+    /// use imxrt_dma::{peripheral, sim::Simulator};
+    /// use core::{cell::Cell, future::Future, pin::pin, task::{Context, Poll, Waker}};
+    /// use std::{sync::Arc, task::Wake};
+    ///
+    /// struct Noop;
+    /// impl Wake for Noop {
+    ///     fn wake(self: Arc<Self>) {}
+    /// }
+    ///
+    /// struct Uart(Cell<u8>);
+    /// const UART_RX: u32 = 3;
+    ///
+    /// unsafe impl peripheral::Source<u8> for Uart {
+    ///     fn source_signal(&self) -> u32 { UART_RX }
+    ///     fn source_address(&self) -> *const u8 { self.0.as_ptr() }
+    ///     fn enable_source(&mut self) {}
+    ///     fn disable_source(&mut self) {}
+    /// }
+    ///
+    /// let sim = Simulator::new();
+    /// let dma = sim.dma::<32>();
+    /// // Safety: we only allocate one DMA channel 7 object.
+    /// let mut channel = unsafe { dma.channel(7) };
+    ///
+    /// let mut uart = Uart(Cell::new(0));
+    /// let mut buffer = [0u8; 2];
+    /// // Safety: the UART and buffer outlive the transfer.
+    /// unsafe {
+    ///     sim.map(&uart.0);
+    ///     sim.map(&buffer);
+    /// }
+    ///
+    /// let data = uart.0.as_ptr();
+    /// let waker = Waker::from(Arc::new(Noop));
+    /// let mut cx = Context::from_waker(&waker);
+    /// {
+    ///     let mut read = pin!(peripheral::read(&mut channel, &mut uart, &mut buffer));
+    ///     for byte in [0xAB, 0xCD] {
+    ///         assert!(read.as_mut().poll(&mut cx).is_pending());
+    ///         // Safety: the transfer only accesses the data register when
+    ///         // the simulator services the request.
+    ///         unsafe { *data = byte };
+    ///         sim.request(UART_RX);
+    ///     }
+    ///     assert!(matches!(read.as_mut().poll(&mut cx), Poll::Ready(Ok(()))));
+    /// }
+    /// assert_eq!(buffer, [0xAB, 0xCD]);
+    /// ```
+    pub fn request(&self, source: u32) {
+        if ral::read_reg!(crate::ral::dma, &self.dma, CR, HALT == 1) {
+            return;
+        }
+        let erq = self.dma.ERQ.read();
+        for channel in 0..32 {
+            let chcfg = self.dmamux.chcfg[channel].read();
+            if erq & (1 << channel) != 0
+                && chcfg & dmamux::RegisterBlock::ENBL != 0
+                && chcfg & dmamux::RegisterBlock::A_ON == 0
+                && chcfg & SOURCE == source
+            {
+                self.service(channel);
+            }
+        }
+    }
+
+    /// Execute one minor loop, then signal any interrupts
+    fn service(&self, channel: usize) {
+        match self.execute(channel) {
+            Ok(true) => {
+                self.dma.INT.set(self.dma.INT.read() | 1 << channel);
+                self.interrupt(Interrupt::Channel(channel));
+            }
+            Ok(false) => {}
+            Err(status) => {
+                let tcd = &self.dma.TCD[channel];
+                tcd.CSR.set(tcd.CSR.read() & !tcd::CSR::START::mask);
+                self.dma
+                    .ES
+                    .set(es::VLD | (channel as u32) << es::ERRCHN_OFFSET | status);
+                self.dma.ERR.set(self.dma.ERR.read() | 1 << channel);
+                if ral::read_reg!(crate::ral::dma, &self.dma, CR, HOE == 1) {
+                    let cr = self.dma.CR.read();
+                    self.dma.CR.set(cr | dma::CR::HALT::mask);
+                }
+                if self.dma.EEI.read() & (1 << channel) != 0 {
+                    self.interrupt(Interrupt::Error);
+                }
+            }
+        }
+    }
+
+    /// Execute the channel's next minor loop
+    ///
+    /// Returns `true` if the channel generated an interrupt, or the error status
+    /// bits if the channel has an error.
+    fn execute(&self, channel: usize) -> Result<bool, u32> {
+        use tcd::CSR;
+
+        let tcd = &self.dma.TCD[channel];
+        // The channel clears START and DONE when it begins execution.
+        let mut csr = tcd.CSR.read() & !(CSR::START::mask | CSR::DONE::mask);
+        tcd.CSR.set(csr);
+
+        let sattr = tcd.SATTR.read();
+        let dattr = tcd.DATTR.read();
+        let ssize = transfer_size(sattr).ok_or(es::SAE)?;
+        let dsize = transfer_size(dattr).ok_or(es::DAE)?;
+        let smod = sattr >> tcd::SATTR::MOD::offset;
+        let dmod = dattr >> tcd::DATTR::MOD::offset;
+
+        let (nbytes, source_offset, destination_offset) = self.minor_loop(tcd.NBYTES.read());
+        let citer = Iterations::new(tcd.CITER.read());
+        let biter = Iterations::new(tcd.BITER.read());
+
+        if nbytes == 0
+            || nbytes % ssize != 0
+            || nbytes % dsize != 0
+            || citer.count == 0
+            || citer.link.is_some() != biter.link.is_some()
+        {
+            return Err(es::NCE);
+        }
+
+        let mut saddr = tcd.SADDR.read();
+        let mut daddr = tcd.DADDR.read();
+        let soff = tcd.SOFF.read() as i32;
+        let doff = tcd.DOFF.read() as i32;
+        if (saddr as usize) % ssize != 0 {
+            return Err(es::SAE);
+        } else if soff % ssize as i32 != 0 {
+            return Err(es::SOE);
+        } else if (daddr as usize) % dsize != 0 {
+            return Err(es::DAE);
+        } else if doff % dsize as i32 != 0 {
+            return Err(es::DOE);
+        } else if csr & CSR::ESG::mask != 0 && tcd.DLAST_SGA.read() % 32 != 0 {
+            return Err(es::SGE);
+        }
+
+        let mut data = vec![0; nbytes];
+        for chunk in data.chunks_mut(ssize) {
+            self.read(saddr, chunk).ok_or(es::SBE)?;
+            saddr = advance(saddr, soff, smod);
+        }
+        for chunk in data.chunks(dsize) {
+            self.write(daddr, chunk).ok_or(es::DBE)?;
+            daddr = advance(daddr, doff, dmod);
+        }
+        saddr = advance(saddr, source_offset, smod);
+        daddr = advance(daddr, destination_offset, dmod);
+
+        let remaining = citer.count - 1;
+        let mut interrupt = csr & CSR::INTHALF::mask != 0 && remaining == biter.count / 2;
+
+        if remaining > 0 {
+            tcd.SADDR.set(saddr);
+            tcd.DADDR.set(daddr);
+            tcd.CITER.set(citer.with_count(remaining));
+            if let Some(link) = citer.link {
+                self.start(link);
+            }
+            return Ok(interrupt);
+        }
+
+        // Major loop complete.
+        interrupt |= csr & CSR::INTMAJOR::mask != 0;
+        if csr & CSR::DREQ::mask != 0 {
+            self.dma.ERQ.set(self.dma.ERQ.read() & !(1 << channel));
+        }
+        if csr & CSR::MAJORELINK::mask != 0 {
+            self.start(((csr & CSR::MAJORLINKCH::mask) >> CSR::MAJORLINKCH::offset) as usize);
+        }
+
+        if csr & CSR::ESG::mask != 0 {
+            let mut next = [0; core::mem::size_of::<tcd::RegisterBlock>()];
+            self.read(tcd.DLAST_SGA.read() as u32, &mut next)
+                .ok_or(es::SGE)?;
+            // Safety: the TCD is a block of integer cells, and the bytes came
+            // from an in-memory TCD.
+            unsafe {
+                core::ptr::copy_nonoverlapping(
+                    next.as_ptr(),
+                    (tcd as *const tcd::RegisterBlock).cast_mut().cast(),
+                    next.len(),
+                )
+            };
+        } else {
+            tcd.SADDR.set(advance(saddr, tcd.SLAST.read(), smod));
+            tcd.DADDR.set(advance(daddr, tcd.DLAST_SGA.read(), dmod));
+            tcd.CITER.set(tcd.BITER.read());
+            csr |= CSR::DONE::mask;
+            tcd.CSR.set(csr);
+        }
+
+        Ok(interrupt)
+    }
+
+    /// Decode the minor loop byte count, and the source and destination minor
+    /// loop offsets
+    fn minor_loop(&self, raw: u32) -> (usize, i32, i32) {
+        use tcd::NBYTES;
+
+        if !ral::read_reg!(crate::ral::dma, &self.dma, CR, EMLM == 1) {
+            return (raw as usize, 0, 0);
+        }
+        let smloe = raw & NBYTES::SMLOE::mask != 0;
+        let dmloe = raw & NBYTES::DMLOE::mask != 0;
+        if !smloe && !dmloe {
+            return (
+                (raw & !(NBYTES::SMLOE::mask | NBYTES::DMLOE::mask)) as usize,
+                0,
+                0,
+            );
+        }
+
+        // Sign-extend the 20-bit offset.
+        let mloff = ((raw & NBYTES::MLOFF::mask) << 2) as i32 >> (NBYTES::MLOFF::offset + 2);
+        let nbytes = (raw & NBYTES::NBYTES_MLOFFYES::mask) as usize;
+        (
+            nbytes,
+            if smloe { mloff } else { 0 },
+            if dmloe { mloff } else { 0 },
+        )
+    }
+
+    /// Set the channel's START bit
+    fn start(&self, channel: usize) {
+        let tcd = &self.dma.TCD[channel];
+        tcd.CSR.set(tcd.CSR.read() | tcd::CSR::START::mask);
+    }
+
+    fn interrupt(&self, interrupt: Interrupt) {
+        if let Some(handler) = self.interrupts.get() {
+            handler(interrupt);
+        }
+    }
+
+    /// Returns the host address of `len` bytes at bus address `address`
+    fn resolve(&self, address: u32, len: usize) -> Option<*mut u8> {
+        lock(&self.memory).iter().find_map(|region| {
+            let offset = address.wrapping_sub(region.bus()) as usize;
+            (offset.checked_add(len)? <= region.len).then_some((region.host + offset) as *mut u8)
+        })
+    }
+
+    fn read(&self, address: u32, buffer: &mut [u8]) -> Option<()> {
+        let host = self.resolve(address, buffer.len())?;
+        // Safety: mapped memory is valid for access. See map().
+        unsafe { core::ptr::copy(host, buffer.as_mut_ptr(), buffer.len()) };
+        Some(())
+    }
+
+    fn write(&self, address: u32, buffer: &[u8]) -> Option<()> {
+        let host = self.resolve(address, buffer.len())?;
+        // Safety: mapped memory is valid for access. See map().
+        unsafe { core::ptr::copy(buffer.as_ptr(), host, buffer.len()) };
+        Some(())
+    }
+
+    /// Returns `true` if `address` is one of this simulator's registers
+    fn owns(&self, address: usize) -> bool {
+        let dma = core::ptr::addr_of!(self.dma) as usize;
+        let dmamux = core::ptr::addr_of!(self.dmamux) as usize;
+        (dma..dma + core::mem::size_of::<dma::RegisterBlock>()).contains(&address)
+            || (dmamux..dmamux + core::mem::size_of::<dmamux::RegisterBlock>()).contains(&address)
+    }
+
+    /// Apply the effects of a write to the register at `address`
+    fn on_write(&self, address: usize) {
+        let dma = &self.dma;
+        let is = |register: *const ()| register as usize == address;
+        let modify = |register: &RWRegister<u32>, command: u8, set: bool| {
+            let channels = channels(command);
+            let value = register.read();
+            register.set(if set {
+                value | channels
+            } else {
+                value & !channels
+            });
+        };
+
+        if is(core::ptr::addr_of!(dma.SERQ).cast()) {
+            modify(&dma.ERQ, dma.SERQ.get(), true);
+        } else if is(core::ptr::addr_of!(dma.CERQ).cast()) {
+            modify(&dma.ERQ, dma.CERQ.get(), false);
+        } else if is(core::ptr::addr_of!(dma.SEEI).cast()) {
+            modify(&dma.EEI, dma.SEEI.get(), true);
+        } else if is(core::ptr::addr_of!(dma.CEEI).cast()) {
+            modify(&dma.EEI, dma.CEEI.get(), false);
+        } else if is(core::ptr::addr_of!(dma.CERR).cast()) {
+            modify(&dma.ERR, dma.CERR.get(), false);
+        } else if is(core::ptr::addr_of!(dma.CINT).cast()) {
+            modify(&dma.INT, dma.CINT.get(), false);
+        } else if is(core::ptr::addr_of!(dma.CDNE).cast()) {
+            let channels = channels(dma.CDNE.get());
+            for (channel, tcd) in dma.TCD.iter().enumerate() {
+                if channels & (1 << channel) != 0 {
+                    tcd.CSR.set(tcd.CSR.read() & !tcd::CSR::DONE::mask);
+                }
+            }
+        } else if is(core::ptr::addr_of!(dma.SSRT).cast()) {
+            let channels = channels(dma.SSRT.get());
+            for channel in (0..32).filter(|channel| channels & (1 << channel) != 0) {
+                self.start(channel);
+            }
+        } else if is(core::ptr::addr_of!(dma.CR).cast()) {
+            // No channel is active between minor loops, so the cancel
+            // completes immediately.
+            dma.CR
+                .set(dma.CR.read() & !(dma::CR::CX::mask | dma::CR::ECX::mask));
+        }
+    }
+}
+
+/// Returns the channels selected by a command register value
+fn channels(command: u8) -> u32 {
+    if command & command::NOP != 0 {
+        0
+    } else if command & command::ALL != 0 {
+        u32::MAX
+    } else {
+        1 << (command & command::CHANNEL)
+    }
+}
+
+/// Returns the transfer size, in bytes, encoded in a SATTR or DATTR value
+fn transfer_size(attr: u8) -> Option<usize> {
+    match attr & tcd::SATTR::SIZE::mask {
+        size @ 0..=5 => Some(1 << size),
+        _ => None,
+    }
+}
+
+/// Apply `offset` to `address`, respecting the address modulo
+fn advance(address: u32, offset: i32, modulo: u8) -> u32 {
+    let next = address.wrapping_add(offset as u32);
+    if modulo == 0 {
+        next
+    } else {
+        let mask = (1u32 << modulo) - 1;
+        (address & !mask) | (next & mask)
+    }
+}
+
+/// A decoded CITER or BITER value
+struct Iterations {
+    raw: u16,
+    count: u16,
+    link: Option<usize>,
+}
+
+impl Iterations {
+    fn new(raw: u16) -> Self {
+        use tcd::CITER;
+        if raw & CITER::ELINK::mask != 0 {
+            Iterations {
+                raw,
+                count: raw & CITER::CITER_ELINKYES::mask,
+                link: Some(((raw & CITER::LINKCH::mask) >> CITER::LINKCH::offset) as usize),
+            }
+        } else {
+            Iterations {
+                raw,
+                count: raw & CITER::CITER::mask,
+                link: None,
+            }
+        }
+    }
+
+    /// Returns the raw value with a new iteration count
+    fn with_count(&self, count: u16) -> u16 {
+        use tcd::CITER;
+        let mask = if self.link.is_some() {
+            CITER::CITER_ELINKYES::mask
+        } else {
+            CITER::CITER::mask
+        };
+        (self.raw & !mask) | count
+    }
+}
+
+/// Handle a write to a simulated register
+fn on_write(register: *const ()) {
+    let address = register as usize;
+    let simulator = lock(&SIMULATORS)
+        .iter()
+        .copied()
+        .find(|simulator| simulator.owns(address));
+    if let Some(simulator) = simulator {
+        simulator.on_write(address);
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::Simulator;
+    use crate::{
+        channel::Channel,
+        controller, memcpy, owned, peripheral,
+        ral::tcd,
+        scatter_gather::{self, Tcd},
+        CircularBuffer, Dma,
+    };
+
+    use core::{
+        cell::Cell,
+        future::Future,
+        pin::{pin, Pin},
+        sync::atomic::{AtomicUsize, Ordering},
+        task::{Context, Poll, Waker},
+    };
+    use std::{boxed::Box, sync::Arc, task::Wake, vec, vec::Vec};
+
+    /// Counts its wakeups
+    #[derive(Default)]
+    struct Counter(AtomicUsize);
+
+    impl Wake for Counter {
+        fn wake(self: Arc<Self>) {
+            self.wake_by_ref();
+        }
+        fn wake_by_ref(self: &Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    impl Counter {
+        fn new() -> (Arc<Self>, Waker) {
+            let counter = Arc::new(Counter::default());
+            let waker = Waker::from(Arc::clone(&counter));
+            (counter, waker)
+        }
+        fn wakes(&self) -> usize {
+            self.0.load(Ordering::SeqCst)
+        }
+    }
+
+    fn poll<F: Future>(future: Pin<&mut F>, waker: &Waker) -> Poll<F::Output> {
+        future.poll(&mut Context::from_waker(waker))
+    }
+
+    /// A peripheral with a receive and a transmit data register
+    struct Uart {
+        rx: Cell<u16>,
+        tx: Cell<u16>,
+    }
+
+    const UART_RX: u32 = 3;
+    const UART_TX: u32 = 4;
+
+    impl Uart {
+        fn new() -> Self {
+            Uart {
+                rx: Cell::new(0),
+                tx: Cell::new(0),
+            }
+        }
+    }
+
+    unsafe impl peripheral::Source<u16> for Uart {
+        fn source_signal(&self) -> u32 {
+            UART_RX
+        }
+        fn source_address(&self) -> *const u16 {
+            self.rx.as_ptr()
+        }
+        fn enable_source(&mut self) {}
+        fn disable_source(&mut self) {}
+    }
+
+    unsafe impl peripheral::Destination<u16> for Uart {
+        fn destination_signal(&self) -> u32 {
+            UART_TX
+        }
+        fn destination_address(&self) -> *const u16 {
+            self.tx.as_ptr()
+        }
+        fn enable_destination(&mut self) {}
+        fn disable_destination(&mut self) {}
+    }
+
+    unsafe impl peripheral::Bidirectional<u16> for Uart {}
+
+    /// Returns a channel that interrupts on completion and on error
+    fn channel(dma: &'static Dma<32>, index: usize) -> Channel {
+        // Safety: each test creates its own simulator, and only allocates
+        // one object for each channel.
+        let mut channel = unsafe { dma.channel(index) };
+        channel.set_interrupt_on_completion(true);
+        channel.set_interrupt_on_error(true);
+        channel
+    }
+
+    impl Simulator {
+        fn is_complete(&self, channel: usize) -> bool {
+            self.dma.TCD[channel].CSR.read() & tcd::CSR::DONE::mask != 0
+        }
+        fn is_error(&self, channel: usize) -> bool {
+            self.dma.ERR.read() & (1 << channel) != 0
+        }
+        fn is_enabled(&self, channel: usize) -> bool {
+            self.dma.ERQ.read() & (1 << channel) != 0
+        }
+        fn csr(&self, channel: usize) -> u16 {
+            self.dma.TCD[channel].CSR.read()
+        }
+        /// Returns the source and destination transfer sizes, in bytes
+        fn transfer_sizes(&self, channel: usize) -> (usize, usize) {
+            let tcd = &self.dma.TCD[channel];
+            (
+                super::transfer_size(tcd.SATTR.read()).unwrap(),
+                super::transfer_size(tcd.DATTR.read()).unwrap(),
+            )
+        }
+    }
+
+    #[test]
+    fn memcpy() {
+        let sim = Simulator::new();
+        let mut channel = channel(sim.dma(), 7);
+        let (counter, waker) = Counter::new();
+
+        let source: Vec<u32> = (0..64).collect();
+        let mut destination = [0u32; 64];
+        // Safety: the buffers outlive the transfer.
+        unsafe {
+            sim.map(&source[..]);
+            sim.map(&destination);
+        }
+
+        {
+            let mut transfer = pin!(memcpy::memcpy(&source, &mut destination, &mut channel));
+            assert!(poll(transfer.as_mut(), &waker).is_pending());
+            assert_eq!(counter.wakes(), 0);
+
+            sim.run();
+            assert_eq!(counter.wakes(), 1);
+            assert!(sim.is_complete(7));
+            assert!(!sim.is_error(7));
+
+            assert!(matches!(
+                poll(transfer.as_mut(), &waker),
+                Poll::Ready(Ok(()))
+            ));
+            assert!(!sim.is_complete(7));
+        }
+        assert_eq!(destination[..], source[..]);
+    }
+
+    #[test]
+    fn memcpy_bus_error() {
+        let sim = Simulator::new();
+        let mut channel = channel(sim.dma(), 7);
+        let (counter, waker) = Counter::new();
+
+        let source = [1u32; 4];
+        let mut destination = [0u32; 4];
+        // Safety: the buffer outlives the transfer. The destination isn't mapped.
+        unsafe { sim.map(&source) };
+
+        {
+            let mut transfer = pin!(memcpy::memcpy(&source, &mut destination, &mut channel));
+            assert!(poll(transfer.as_mut(), &waker).is_pending());
+
+            sim.run();
+            assert_eq!(counter.wakes(), 1);
+            assert!(sim.is_error(7));
+            assert!(!sim.is_complete(7));
+
+            match poll(transfer.as_mut(), &waker) {
+                Poll::Ready(Err(error)) => {
+                    assert!(error.is_destination_bus());
+                    assert_eq!(error.channel_number(), 7);
+                }
+                poll => panic!("{poll:?}"),
+            }
+            assert!(!sim.is_error(7));
+        }
+        assert_eq!(destination, [0; 4]);
+    }
+
+    /// A buffer aligned to the widest transfer size
+    #[repr(align(32))]
+    struct Aligned([u8; 96]);
+
+    /// Copy `len` bytes from `source_at` to `destination_at` in 32-byte aligned buffers,
+    /// then return the transfer size
+    fn memcpy_bytes(source_at: usize, destination_at: usize, len: usize) -> usize {
+        let sim = Simulator::new();
+        let mut channel = channel(sim.dma(), 7);
+        let (_, waker) = Counter::new();
+
+        let mut source = Aligned([0; 96]);
+        for (idx, byte) in source.0.iter_mut().enumerate() {
+            *byte = idx as u8 + 1;
+        }
+        let mut destination = Aligned([0; 96]);
+        // Safety: the buffers outlive the transfer.
+        unsafe {
+            sim.map(&source);
+            sim.map(&destination);
+        }
+
+        {
+            let source = &source.0[source_at..source_at + len];
+            let destination = &mut destination.0[destination_at..destination_at + len];
+            let mut transfer = pin!(memcpy::memcpy(source, destination, &mut channel));
+            assert!(poll(transfer.as_mut(), &waker).is_pending());
+            sim.run();
+            assert!(matches!(
+                poll(transfer.as_mut(), &waker),
+                Poll::Ready(Ok(()))
+            ));
+        }
+
+        let (size, destination_size) = sim.transfer_sizes(7);
+        assert_eq!(size, destination_size);
+        // Every byte, including the tail, and nothing around the copy.
+        assert_eq!(
+            destination.0[destination_at..destination_at + len],
+            source.0[source_at..source_at + len]
+        );
+        assert!(destination.0[..destination_at]
+            .iter()
+            .chain(&destination.0[destination_at + len..])
+            .all(|&byte| byte == 0));
+        size
+    }
+
+    #[test]
+    fn memcpy_transfer_size() {
+        // Aligned buffers, and a multiple of 32 bytes.
+        assert_eq!(memcpy_bytes(0, 0, 64), 32);
+        // The length is only a multiple of 8 bytes.
+        assert_eq!(memcpy_bytes(0, 0, 40), 8);
+        // The length leaves an odd tail.
+        assert_eq!(memcpy_bytes(0, 0, 37), 1);
+        // The narrower alignment of the two buffers wins.
+        assert_eq!(memcpy_bytes(4, 32, 32), 4);
+        assert_eq!(memcpy_bytes(16, 2, 32), 2);
+        assert_eq!(memcpy_bytes(16, 48, 48), 16);
+    }
+
+    #[test]
+    fn memcpy_too_large() {
+        let sim = Simulator::new();
+        let dma = sim.dma();
+        let mut configuration = controller::Configuration::new();
+        configuration.minor_loop_mapping = true;
+        // Safety: no transfers are active.
+        unsafe { dma.set_configuration(&configuration) };
+        let mut channel = channel(dma, 7);
+        let (_, waker) = Counter::new();
+
+        let max = channel.max_minor_loop_bytes() as usize;
+        // The allocator doesn't touch zeroed pages that the copy never accesses.
+        let source = vec![0u8; max + 1];
+        let mut destination = vec![0u8; max + 1];
+        {
+            let mut transfer = pin!(memcpy::memcpy(&source, &mut destination, &mut channel));
+            match poll(transfer.as_mut(), &waker) {
+                Poll::Ready(Err(error)) => assert!(error.is_loop_configuration()),
+                poll => panic!("{poll:?}"),
+            }
+            assert_eq!(transfer.transferred(), 0);
+        }
+        assert!(!sim.is_enabled(7));
+        assert_eq!(sim.run(), 0);
+    }
+
+    #[test]
+    fn memcpy_2d() {
+        let sim = Simulator::new();
+        let dma = sim.dma();
+        let mut configuration = controller::Configuration::new();
+        configuration.minor_loop_mapping = true;
+        // Safety: no transfers are active.
+        unsafe { dma.set_configuration(&configuration) };
+        let mut channel = channel(dma, 7);
+        let (_, waker) = Counter::new();
+
+        // A 3 × 2 sprite, copied into an 4 × 3 framebuffer.
+        let sprite = [1u16, 2, 3, 4, 5, 6];
+        let mut framebuffer = [0u16; 4 * 3];
+        // Safety: the buffers outlive the transfer.
+        unsafe {
+            sim.map(&sprite);
+            sim.map(&framebuffer);
+        }
+
+        {
+            let mut transfer = pin!(memcpy::memcpy_2d(
+                &sprite,
+                3,
+                &mut framebuffer,
+                4,
+                2,
+                3,
+                &mut channel
+            ));
+            assert!(poll(transfer.as_mut(), &waker).is_pending());
+            assert_eq!(sim.run(), 2);
+            assert!(matches!(
+                poll(transfer.as_mut(), &waker),
+                Poll::Ready(Ok(()))
+            ));
+        }
+        assert_eq!(framebuffer, [1, 2, 3, 0, 4, 5, 6, 0, 0, 0, 0, 0]);
+        // The channel rewinds to the start of both buffers, on the 32-bit bus.
+        assert_eq!(channel.source_address() as u32, sprite.as_ptr() as u32);
+        assert_eq!(
+            channel.destination_address() as u32,
+            framebuffer.as_ptr() as u32
+        );
+    }
+
+    #[test]
+    fn fill() {
+        let sim = Simulator::new();
+        let mut channel = channel(sim.dma(), 7);
+        let (_, waker) = Counter::new();
+
+        let value = 0xA5A5_0F0Fu32;
+        let mut destination = [0u32; 16];
+        // Safety: the buffers outlive the transfer.
+        unsafe {
+            sim.map(&value);
+            sim.map(&destination);
+        }
+
+        {
+            let mut transfer = pin!(memcpy::fill(&value, &mut destination, &mut channel));
+            assert!(poll(transfer.as_mut(), &waker).is_pending());
+            sim.run();
+            assert!(matches!(
+                poll(transfer.as_mut(), &waker),
+                Poll::Ready(Ok(()))
+            ));
+        }
+        assert_eq!(destination, [value; 16]);
+    }
+
+    #[test]
+    fn fill_pattern() {
+        let sim = Simulator::new();
+        let mut channel = channel(sim.dma(), 7);
+        let (_, waker) = Counter::new();
+
+        let pattern = CircularBuffer::new([1u16, 2, 3, 4]);
+        let single = CircularBuffer::new([9u16]);
+        let mut destination = [0u16; 10];
+        // Safety: the buffers outlive the transfers.
+        unsafe {
+            sim.map(&pattern);
+            sim.map(&single);
+            sim.map(&destination);
+        }
+
+        {
+            let mut transfer = pin!(memcpy::fill_pattern(
+                &pattern,
+                &mut destination,
+                &mut channel
+            ));
+            assert!(poll(transfer.as_mut(), &waker).is_pending());
+            sim.run();
+            assert!(matches!(
+                poll(transfer.as_mut(), &waker),
+                Poll::Ready(Ok(()))
+            ));
+        }
+        // The last repetition is partial.
+        assert_eq!(destination, [1, 2, 3, 4, 1, 2, 3, 4, 1, 2]);
+
+        {
+            // The smallest pattern wraps on every element.
+            let mut transfer = pin!(memcpy::fill_pattern(
+                &single,
+                &mut destination,
+                &mut channel
+            ));
+            assert!(poll(transfer.as_mut(), &waker).is_pending());
+            sim.run();
+            assert!(matches!(
+                poll(transfer.as_mut(), &waker),
+                Poll::Ready(Ok(()))
+            ));
+        }
+        assert_eq!(destination, [9; 10]);
+    }
+
+    #[test]
+    fn read() {
+        let sim = Simulator::new();
+        let mut channel = channel(sim.dma(), 3);
+        let (counter, waker) = Counter::new();
+
+        let mut uart = Uart::new();
+        let mut buffer = [0u16; 8];
+        // Safety: the peripheral and buffer outlive the transfer.
+        unsafe {
+            sim.map(&uart.rx);
+            sim.map(&buffer);
+        }
+
+        let rx = uart.rx.as_ptr();
+        {
+            let mut read = pin!(peripheral::read(&mut channel, &mut uart, &mut buffer));
+            for value in 0..8 {
+                assert!(poll(read.as_mut(), &waker).is_pending());
+                assert_eq!(read.transferred(), value as usize);
+                // Safety: the simulator only reads the register when it
+                // services the request.
+                unsafe { *rx = value * 3 };
+                sim.request(UART_RX);
+            }
+            assert_eq!(counter.wakes(), 1);
+            assert!(matches!(poll(read.as_mut(), &waker), Poll::Ready(Ok(()))));
+            assert_eq!(read.transferred(), 8);
+        }
+        assert_eq!(buffer, [0, 3, 6, 9, 12, 15, 18, 21]);
+        assert!(!sim.is_complete(3));
+        assert!(!sim.is_enabled(3));
+    }
+
+    #[test]
+    fn cancel_leaves_other_channels_running() {
+        let sim = Simulator::new();
+        let dma = sim.dma();
+        let mut rx_channel = channel(dma, 3);
+        let mut tx_channel = channel(dma, 5);
+        let (_, waker) = Counter::new();
+
+        let mut receiver = Uart::new();
+        let mut sender = Uart::new();
+        let mut received = [0u16; 4];
+        let sent = [1u16, 2, 3, 4];
+        // Safety: the peripherals and buffers outlive the transfers.
+        unsafe {
+            sim.map(&receiver.rx);
+            sim.map(&sender.tx);
+            sim.map(&received);
+            sim.map(&sent);
+        }
+
+        let rx = receiver.rx.as_ptr();
+        {
+            let mut read = pin!(peripheral::read(
+                &mut rx_channel,
+                &mut receiver,
+                &mut received
+            ));
+            let mut write = pin!(peripheral::write(&mut tx_channel, &sent, &mut sender));
+            assert!(poll(read.as_mut(), &waker).is_pending());
+            assert!(poll(write.as_mut(), &waker).is_pending());
+
+            // Safety: the simulator only reads the register when it
+            // services the request.
+            unsafe { *rx = 7 };
+            sim.request(UART_RX);
+            sim.request(UART_TX);
+
+            assert_eq!(read.as_mut().cancel(), 1);
+            assert!(!sim.is_enabled(3));
+            assert!(sim.is_enabled(5));
+            match poll(read.as_mut(), &waker) {
+                Poll::Ready(Err(error)) => assert!(error.is_cancelled()),
+                poll => panic!("{poll:?}"),
+            }
+
+            // The cancelled channel ignores requests. The other channel
+            // continues without an error.
+            sim.request(UART_RX);
+            for _ in 1..4 {
+                sim.request(UART_TX);
+            }
+            assert!(!sim.is_error(3));
+            assert!(!sim.is_error(5));
+            assert!(matches!(poll(write.as_mut(), &waker), Poll::Ready(Ok(()))));
+        }
+        assert_eq!(received, [7, 0, 0, 0]);
+        assert_eq!(sender.tx.get(), 4);
+    }
+
+    #[test]
+    fn owned_read() {
+        let sim = Simulator::new();
+        let mut channel = channel(sim.dma(), 3);
+        let (_, waker) = Counter::new();
+
+        let mut uart = Uart::new();
+        let buffer: &'static mut [u16; 4] = Box::leak(Box::new([0; 4]));
+        let address = buffer.as_ptr();
+        // Safety: the peripheral outlives the transfers, and the buffer is leaked.
+        unsafe {
+            sim.map(&uart.rx);
+            sim.map(&*buffer);
+        }
+
+        let rx = uart.rx.as_ptr();
+        let buffer = {
+            let mut read = pin!(owned::read(&mut channel, &mut uart, buffer));
+            for value in 1..=4 {
+                assert!(poll(read.as_mut(), &waker).is_pending());
+                // Safety: the simulator only reads the register when it
+                // services the request.
+                unsafe { *rx = value };
+                sim.request(UART_RX);
+            }
+            match poll(read.as_mut(), &waker) {
+                Poll::Ready((result, buffer)) => {
+                    result.unwrap();
+                    buffer
+                }
+                Poll::Pending => panic!("Read is pending"),
+            }
+        };
+        assert_eq!(buffer.as_ptr(), address);
+        assert_eq!(*buffer, [1, 2, 3, 4]);
+
+        let buffer = {
+            let mut read = pin!(owned::read(&mut channel, &mut uart, buffer));
+            assert!(poll(read.as_mut(), &waker).is_pending());
+            // Safety: see above.
+            unsafe { *rx = 5 };
+            sim.request(UART_RX);
+            assert_eq!(read.as_mut().cancel(), 1);
+            match poll(read.as_mut(), &waker) {
+                Poll::Ready((result, buffer)) => {
+                    assert!(result.unwrap_err().is_cancelled());
+                    buffer
+                }
+                Poll::Pending => panic!("Read is pending"),
+            }
+        };
+        assert!(!sim.is_enabled(3));
+        assert_eq!(buffer.as_ptr(), address);
+        assert_eq!(*buffer, [5, 2, 3, 4]);
+    }
+
+    #[test]
+    fn owned_memcpy() {
+        let sim = Simulator::new();
+        let mut channel = channel(sim.dma(), 7);
+        let (_, waker) = Counter::new();
+
+        let source: &'static mut [u32; 8] = Box::leak(Box::new([7; 8]));
+        let destination: &'static mut [u32; 8] = Box::leak(Box::new([0; 8]));
+        // Safety: the buffers are leaked.
+        unsafe {
+            sim.map(&*source);
+            sim.map(&*destination);
+        }
+
+        let (source, destination) = {
+            let mut transfer = pin!(owned::memcpy(source, destination, &mut channel));
+            assert!(poll(transfer.as_mut(), &waker).is_pending());
+            sim.run();
+            match poll(transfer.as_mut(), &waker) {
+                Poll::Ready((result, buffers)) => {
+                    result.unwrap();
+                    buffers
+                }
+                Poll::Pending => panic!("Memcpy is pending"),
+            }
+        };
+        assert_eq!(*destination, [7; 8]);
+
+        source.fill(9);
+        let (source, destination) = {
+            let mut transfer = pin!(owned::memcpy(source, destination, &mut channel));
+            assert!(poll(transfer.as_mut(), &waker).is_pending());
+            // Cancel before the simulator services the channel.
+            assert_eq!(transfer.as_mut().cancel(), 0);
+            match poll(transfer.as_mut(), &waker) {
+                Poll::Ready((result, buffers)) => {
+                    assert!(result.unwrap_err().is_cancelled());
+                    buffers
+                }
+                Poll::Pending => panic!("Memcpy is pending"),
+            }
+        };
+        assert!(!sim.is_enabled(7));
+        assert_eq!(sim.run(), 0);
+        assert_eq!(*source, [9; 8]);
+        assert_eq!(*destination, [7; 8]);
+    }
+
+    #[test]
+    fn read_segments() {
+        let sim = Simulator::new();
+        let mut channel = channel(sim.dma(), 3);
+        let (counter, waker) = Counter::new();
+
+        let mut uart = Uart::new();
+        let max = channel.max_transfer_iterations() as usize;
+        let len = 2 * max + 5;
+        let mut buffer: Vec<u16> = vec![0; len];
+        // Safety: the peripheral and buffer outlive the transfer.
+        unsafe {
+            sim.map(&uart.rx);
+            sim.map(&buffer[..]);
+        }
+
+        let rx = uart.rx.as_ptr();
+        {
+            let mut read = pin!(peripheral::read(&mut channel, &mut uart, &mut buffer));
+            // Safety: the future holds the segments' TCDs, and it's pinned
+            // until the transfer stops.
+            unsafe { sim.map(&*read) };
+            assert!(poll(read.as_mut(), &waker).is_pending());
+            let mut wakes = 0;
+            for value in 0..len {
+                // Safety: the simulator only reads the register when it
+                // services the request.
+                unsafe { *rx = value as u16 };
+                sim.request(UART_RX);
+                // The channel continues into the next segment without a poll.
+                assert_eq!(read.transferred(), value + 1);
+                if counter.wakes() != wakes {
+                    wakes = counter.wakes();
+                    assert!(poll(read.as_mut(), &waker).is_ready() == (value + 1 == len));
+                }
+            }
+            // One interrupt for each segment.
+            assert_eq!(counter.wakes(), 3);
+            assert!(matches!(poll(read.as_mut(), &waker), Poll::Ready(Ok(()))));
+        }
+        assert!(buffer
+            .iter()
+            .enumerate()
+            .all(|(idx, &value)| value == idx as u16));
+    }
+
+    #[test]
+    fn write_segments_without_polls() {
+        let sim = Simulator::new();
+        let mut channel = channel(sim.dma(), 3);
+        let (_, waker) = Counter::new();
+
+        let mut uart = Uart::new();
+        let max = channel.max_transfer_iterations() as usize;
+        let buffer: Vec<u16> = (0..2 * max + 5).map(|value| value as u16).collect();
+        // Safety: the peripheral and buffer outlive the transfer.
+        unsafe {
+            sim.map(&uart.tx);
+            sim.map(&buffer[..]);
+        }
+
+        let tx = uart.tx.as_ptr();
+        let mut sent = Vec::new();
+        {
+            let mut write = pin!(peripheral::write(&mut channel, &buffer, &mut uart));
+            // Safety: the future holds the segments' TCDs, and it's pinned
+            // until the transfer stops.
+            unsafe { sim.map(&*write) };
+            while poll(write.as_mut(), &waker).is_pending() {
+                // Without polls, the channel stops after the segment that
+                // follows the first segment.
+                while sim.is_enabled(3) {
+                    sim.request(UART_TX);
+                    // Safety: the simulator wrote the register.
+                    sent.push(unsafe { *tx });
+                }
+            }
+        }
+        assert_eq!(sent, buffer);
+    }
+
+    #[test]
+    fn read_packed() {
+        let sim = Simulator::new();
+        let mut channel = channel(sim.dma(), 3);
+        let (_, waker) = Counter::new();
+
+        let mut uart = Uart::new();
+        let mut words = [0u32; 3];
+        let mut bytes = [0u8; 6];
+        // Safety: the peripheral and buffers outlive the transfers.
+        unsafe {
+            sim.map(&uart.rx);
+            sim.map(&words);
+            sim.map(&bytes);
+        }
+
+        let rx = uart.rx.as_ptr();
+        {
+            // Each request reads the narrower register twice, so a real peripheral
+            // needs a watermark of two elements.
+            let mut read = pin!(peripheral::read_packed::<_, u16, _>(
+                &mut channel,
+                &mut uart,
+                &mut words
+            ));
+            for value in 1..=3 {
+                assert!(poll(read.as_mut(), &waker).is_pending());
+                // Safety: the simulator only reads the register when it
+                // services the request.
+                unsafe { *rx = value };
+                sim.request(UART_RX);
+                assert_eq!(read.transferred(), value as usize);
+            }
+            assert!(matches!(poll(read.as_mut(), &waker), Poll::Ready(Ok(()))));
+        }
+        assert_eq!(words, [0x0001_0001, 0x0002_0002, 0x0003_0003]);
+
+        {
+            // Each request reads the wider register once, and unpacks it into
+            // bytes.
+            let mut read = pin!(peripheral::read_packed::<_, u16, _>(
+                &mut channel,
+                &mut uart,
+                &mut bytes
+            ));
+            for value in [0x0201, 0x0403, 0x0605] {
+                assert!(poll(read.as_mut(), &waker).is_pending());
+                // Safety: see above.
+                unsafe { *rx = value };
+                sim.request(UART_RX);
+            }
+            assert_eq!(read.transferred(), 3);
+            assert!(matches!(poll(read.as_mut(), &waker), Poll::Ready(Ok(()))));
+        }
+        assert_eq!(bytes, [1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn write_packed() {
+        let sim = Simulator::new();
+        let mut channel = channel(sim.dma(), 3);
+        let (_, waker) = Counter::new();
+
+        let mut uart = Uart::new();
+        let words = [0x0002_0001u32, 0x0004_0003];
+        let bytes = [1u8, 2, 3, 4];
+        // Safety: the peripheral and buffers outlive the transfers.
+        unsafe {
+            sim.map(&uart.tx);
+            sim.map(&words);
+            sim.map(&bytes);
+        }
+
+        let tx = uart.tx.as_ptr();
+        let mut sent = Vec::new();
+        {
+            // Each request writes the narrower register twice, so a real peripheral
+            // needs room for two elements. The register keeps the second write.
+            let mut write = pin!(peripheral::write_packed::<_, u16, _>(
+                &mut channel,
+                &words,
+                &mut uart
+            ));
+            while poll(write.as_mut(), &waker).is_pending() {
+                sim.request(UART_TX);
+                // Safety: the simulator wrote the register.
+                sent.push(unsafe { *tx });
+                assert_eq!(write.transferred(), sent.len());
+            }
+        }
+        assert_eq!(sent, [0x0002, 0x0004]);
+
+        sent.clear();
+        {
+            // Each request packs two bytes into one register write.
+            let mut write = pin!(peripheral::write_packed::<_, u16, _>(
+                &mut channel,
+                &bytes,
+                &mut uart
+            ));
+            while poll(write.as_mut(), &waker).is_pending() {
+                sim.request(UART_TX);
+                // Safety: see above.
+                sent.push(unsafe { *tx });
+            }
+            assert_eq!(write.transferred(), 2);
+        }
+        assert_eq!(sent, [0x0201, 0x0403]);
+    }
+
+    #[test]
+    fn full_duplex() {
+        let sim = Simulator::new();
+        let dma = sim.dma();
+        let mut rx_channel = channel(dma, 5);
+        let mut tx_channel = channel(dma, 6);
+        tx_channel.set_interrupt_on_completion(false);
+        let (counter, waker) = Counter::new();
+
+        let mut uart = Uart::new();
+        let mut buffer = [1u16, 2, 3, 4];
+        // Safety: the peripheral and buffer outlive the transfer.
+        unsafe {
+            sim.map(&uart.rx);
+            sim.map(&uart.tx);
+            sim.map(&buffer);
+        }
+
+        let (rx, tx) = (uart.rx.as_ptr(), uart.tx.as_ptr());
+        let mut sent = Vec::new();
+        {
+            let mut transfer = pin!(peripheral::full_duplex(
+                &mut rx_channel,
+                &mut tx_channel,
+                &mut uart,
+                &mut buffer
+            ));
+            for _ in 0..4 {
+                assert!(poll(transfer.as_mut(), &waker).is_pending());
+                sim.request(UART_TX);
+                // Safety: the simulator isn't accessing the registers. Echo
+                // each sent element, plus 10.
+                unsafe {
+                    sent.push(*tx);
+                    *rx = *tx + 10;
+                }
+                sim.request(UART_RX);
+            }
+            assert_eq!(counter.wakes(), 1);
+            assert!(matches!(
+                poll(transfer.as_mut(), &waker),
+                Poll::Ready(Ok(()))
+            ));
+        }
+        assert_eq!(sent, [1, 2, 3, 4]);
+        assert_eq!(buffer, [11, 12, 13, 14]);
+    }
+
+    #[test]
+    fn read_stream() {
+        let sim = Simulator::new();
+        let mut channel = channel(sim.dma(), 3);
+        let (_, waker) = Counter::new();
+        let before = sim.csr(3);
+        assert_eq!(before & tcd::CSR::INTHALF::mask, 0);
+
+        let mut uart = Uart::new();
+        let mut buffer = [0u16; 8];
+        // Safety: the peripheral and buffer outlive the transfer.
+        unsafe {
+            sim.map(&uart.rx);
+            sim.map(&buffer);
+        }
+
+        let rx = uart.rx.as_ptr();
+        let next = Cell::new(0);
+        let receive = |count| {
+            for _ in 0..count {
+                // Safety: the simulator only reads the register when it
+                // services the request.
+                unsafe { *rx = next.get() };
+                next.set(next.get() + 1);
+                sim.request(UART_RX);
+            }
+        };
+
+        {
+            let mut stream = peripheral::read_stream(&mut channel, &mut uart, &mut buffer);
+            let mut half = [0u16; 4];
+
+            // Each half resolves once the channel fills it.
+            for expected in [[0, 1, 2, 3], [4, 5, 6, 7], [8, 9, 10, 11]] {
+                assert!(poll(pin!(stream.next_half(&mut half)), &waker).is_pending());
+                receive(4);
+                assert!(matches!(
+                    poll(pin!(stream.next_half(&mut half)), &waker),
+                    Poll::Ready(Ok(()))
+                ));
+                assert_eq!(half, expected);
+            }
+
+            // The channel fills the next half, then overwrites it.
+            receive(12);
+            match poll(pin!(stream.next_half(&mut half)), &waker) {
+                Poll::Ready(Err(error)) => assert!(error.is_overrun()),
+                poll => panic!("{poll:?}"),
+            }
+
+            // The stream continues with the half that the channel is filling.
+            receive(4);
+            assert!(matches!(
+                poll(pin!(stream.next_half(&mut half)), &waker),
+                Poll::Ready(Ok(()))
+            ));
+            assert_eq!(half, [24, 25, 26, 27]);
+        }
+        assert!(!sim.is_enabled(3));
+        assert_eq!(sim.csr(3) & !tcd::CSR::DONE::mask, before);
+    }
+
+    #[test]
+    fn read_ring_overrun() {
+        let sim = Simulator::new();
+        let mut channel = channel(sim.dma(), 3);
+
+        let mut uart = Uart::new();
+        let mut buffer = [0u16; 7];
+        // Safety: the peripheral and buffer outlive the transfer.
+        unsafe {
+            sim.map(&uart.rx);
+            sim.map(&buffer);
+        }
+
+        let rx = uart.rx.as_ptr();
+        let next = Cell::new(0);
+        let receive = |count| {
+            for _ in 0..count {
+                // Safety: the simulator only reads the register when it
+                // services the request.
+                unsafe { *rx = next.get() };
+                next.set(next.get() + 1);
+                sim.request(UART_RX);
+            }
+        };
+
+        let before = sim.csr(3);
+        let mut ring = peripheral::read_ring(&mut channel, &mut uart, &mut buffer);
+        let mut data = [0u16; 8];
+
+        // Fill the ring, and consume it, across many laps.
+        for lap in 0..10 {
+            receive(6);
+            assert_eq!(ring.try_read(&mut data).unwrap(), 6);
+            assert_eq!(data[..6], [0, 1, 2, 3, 4, 5].map(|idx| lap * 6 + idx));
+        }
+
+        // Overrun within a lap.
+        receive(4);
+        assert_eq!(ring.try_read(&mut data[..1]).unwrap(), 1);
+        receive(4);
+        assert!(ring.try_read(&mut data).unwrap_err().is_overrun());
+        assert_eq!(ring.try_read(&mut data).unwrap(), 0);
+
+        // Overrun by exactly one lap, and by many laps.
+        for count in [7, 7 * 3 + 2] {
+            receive(count);
+            assert!(ring.try_read(&mut data).unwrap_err().is_overrun());
+            receive(2);
+            assert_eq!(ring.try_read(&mut data).unwrap(), 2);
+            assert_eq!(data[..2], [next.get() - 2, next.get() - 1]);
+        }
+
+        drop(ring);
+        assert_eq!(sim.csr(3) & !tcd::CSR::DONE::mask, before);
+    }
+
+    #[test]
+    fn write_ring_error() {
+        let sim = Simulator::new();
+        let mut channel = channel(sim.dma(), 4);
+        let (_, waker) = Counter::new();
+
+        let mut uart = Uart::new();
+        let mut buffer = [0u16; 8];
+        // Safety: the buffer outlives the transfer. The peripheral isn't mapped.
+        unsafe { sim.map(&buffer) };
+
+        let mut ring = peripheral::write_ring(&mut channel, &mut uart, &mut buffer);
+        assert_eq!(ring.try_write(&[1, 2, 3]), 3);
+        sim.request(UART_TX);
+        assert!(sim.is_error(4));
+
+        // The next write retires the failed transfer.
+        assert_eq!(ring.try_write(&[4]), 1);
+        match poll(pin!(ring.flush()), &waker) {
+            Poll::Ready(Err(error)) => assert!(error.is_destination_bus()),
+            poll => panic!("{poll:?}"),
+        }
+    }
+
+    #[test]
+    fn scatter_gather_chain() {
+        let sim = Simulator::new();
+        let mut channel = channel(sim.dma(), 9);
+        channel.set_interrupt_on_completion(false);
+        let (counter, waker) = Counter::new();
+
+        let first = [1u32; 4];
+        let second = [2u32; 4];
+        let mut destination = [0u32; 8];
+        let mut tcds = [Tcd::new(), Tcd::new()];
+        for (tcd, (source, offset)) in tcds.iter_mut().zip([(&first, 0), (&second, 4)]) {
+            // Safety: the buffers outlive the transfer.
+            unsafe {
+                tcd.set_source_address(source.as_ptr());
+                tcd.set_source_offset(4);
+                tcd.set_source_attributes::<u32>(0);
+                tcd.set_destination_address(destination[offset..].as_mut_ptr());
+                tcd.set_destination_offset(4);
+                tcd.set_destination_attributes::<u32>(0);
+                tcd.set_minor_loop_bytes(16);
+                tcd.set_transfer_iterations(1);
+            }
+            tcd.set_start(true);
+        }
+        tcds[1].set_interrupt_on_completion(true);
+        // Safety: the buffers and descriptors outlive the transfer.
+        unsafe {
+            sim.map(&first);
+            sim.map(&second);
+            sim.map(&destination);
+            sim.map(&tcds);
+        }
+
+        {
+            let mut transfer = pin!(scatter_gather::chain(&mut channel, &mut tcds));
+            assert!(poll(transfer.as_mut(), &waker).is_pending());
+            assert_eq!(sim.run(), 2);
+            assert_eq!(counter.wakes(), 1);
+            assert!(sim.is_complete(9));
+            assert!(matches!(
+                poll(transfer.as_mut(), &waker),
+                Poll::Ready(Ok(()))
+            ));
+        }
+        assert_eq!(destination, [1, 1, 1, 1, 2, 2, 2, 2]);
+    }
+
+    #[test]
+    fn map_is_keyed_by_host_address() {
+        use super::Region;
+
+        let sim = Simulator::new();
+        let buffer = [0u8; 16];
+        // Safety: the simulator never accesses the buffer.
+        unsafe {
+            sim.map(&buffer);
+            sim.map(&buffer[4..]);
+        }
+
+        let region = |host: u64, len| Region {
+            host: host as usize,
+            len,
+        };
+        assert!(!region(0x2000_1000, 16).aliases(&region(0x2000_1008, 16)));
+        assert!(!region(0x2000_1000, 16).aliases(&region(0x2000_1010, 16)));
+        #[cfg(target_pointer_width = "64")]
+        {
+            assert!(region(0x1_2000_1000, 16).aliases(&region(0x2_2000_1008, 16)));
+            assert!(!region(0x1_2000_1000, 16).aliases(&region(0x2_2000_1010, 16)));
+        }
+    }
+}
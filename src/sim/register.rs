@@ -0,0 +1,68 @@
+//! Simulated registers
+//!
+//! These types have the same interface as the `ral_registers` types. Writes
+//! notify the simulator that owns the register, so that writes to command
+//! registers take effect immediately.
+
+use core::cell::UnsafeCell;
+
+#[repr(transparent)]
+pub struct RWRegister<T> {
+    register: UnsafeCell<T>,
+}
+
+impl<T: Copy> RWRegister<T> {
+    #[inline(always)]
+    pub fn read(&self) -> T {
+        unsafe { core::ptr::read_volatile(self.register.get()) }
+    }
+
+    #[inline(always)]
+    pub fn write(&self, val: T) {
+        self.set(val);
+        super::on_write(self.register.get().cast());
+    }
+
+    /// Write the register without notifying the simulator
+    #[inline(always)]
+    pub(crate) fn set(&self, val: T) {
+        unsafe { core::ptr::write_volatile(self.register.get(), val) }
+    }
+}
+
+#[repr(transparent)]
+pub struct RORegister<T> {
+    register: UnsafeCell<T>,
+}
+
+impl<T: Copy> RORegister<T> {
+    #[inline(always)]
+    pub fn read(&self) -> T {
+        unsafe { core::ptr::read_volatile(self.register.get()) }
+    }
+
+    /// Write the register without notifying the simulator
+    #[inline(always)]
+    pub(crate) fn set(&self, val: T) {
+        unsafe { core::ptr::write_volatile(self.register.get(), val) }
+    }
+}
+
+#[repr(transparent)]
+pub struct WORegister<T> {
+    register: UnsafeCell<T>,
+}
+
+impl<T: Copy> WORegister<T> {
+    #[inline(always)]
+    pub fn write(&self, val: T) {
+        unsafe { core::ptr::write_volatile(self.register.get(), val) };
+        super::on_write(self.register.get().cast());
+    }
+
+    /// Read the last value written to the register
+    #[inline(always)]
+    pub(crate) fn get(&self) -> T {
+        unsafe { core::ptr::read_volatile(self.register.get()) }
+    }
+}